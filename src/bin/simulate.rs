@@ -0,0 +1,253 @@
+// Plays the game without a browser, for balancing the game data:
+//   cargo run --bin simulate -- --generations 200 --seed 1234 --strategy greedy --format csv
+
+use anyhow::*;
+use std::env;
+use std::collections::BTreeSet;
+use deepfield::game::{Game, AstroObject};
+use deepfield::game::platform::{Platform, SilentLogger, SystemClock};
+use deepfield::game::seed::Seed;
+
+const USAGE: &str = "usage: simulate [--generations N] [--seed S] [--strategy random|greedy|idle] [--format text|csv|jsonl]";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Strategy {
+    Random, // observe something at random, like the old game loop did
+    Greedy, // always go for whatever needs the most power
+    Idle,   // never observe anything
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Format {
+    Text,
+    Csv,
+    JsonLines,
+}
+
+struct Options {
+    generations: u32,
+    seed: Seed,
+    strategy: Strategy,
+    format: Format,
+}
+
+#[derive(Debug, PartialEq)]
+struct Stats {
+    generation: i32,
+    max_power: i32,
+    effective_power: i32, // after the weather
+    photons: u64,
+    observables: usize,
+    unobservables: usize,
+    observed: usize,
+    acquired: Vec<String>, // telescopes, sites and accessories acquired this generation
+}
+
+fn main() -> Result<()> {
+    let options = parse_args(env::args().skip(1))?;
+    let history = simulate(&options)?;
+
+    match options.format {
+        Format::Text => print_text(&options, &history),
+        Format::Csv => print_csv(&history),
+        Format::JsonLines => print_json_lines(&history)?,
+    }
+    Ok(())
+}
+
+// Play a whole game, and keep the stats for every generation
+fn simulate(options: &Options) -> Result<Vec<Stats>> {
+    let mut game = Game::new(options.seed, Platform::new(Box::new(SilentLogger), Box::new(SystemClock)));
+    game.init();
+
+    // Starting from nothing, so the first generation shows what init() handed out
+    let mut owned_before = BTreeSet::new();
+    let mut history = vec![];
+    for _ in 0..options.generations {
+        let generation = game.generation;
+
+        play(&mut game, options.strategy);
+        if let Some(scope) = game.telescope_for_sale() {
+            if game.state.photons >= scope.price {
+                game.buy_telescope(&scope.key)?;
+            }
+        }
+        if let Some(site) = game.site_for_sale() {
+            if game.state.photons >= site.price {
+                game.buy_site(&site.key)?;
+            }
+        }
+        for acc in game.accessories_for_sale() {
+            if game.state.photons >= acc.price {
+                game.buy_accessory(&acc.key)?;
+            }
+        }
+        game.tick();
+
+        let owned = owned(&game);
+        history.push(Stats {
+            generation,
+            max_power: game.state.max_power,
+            effective_power: game.state.effective_power(),
+            photons: game.state.photons,
+            observables: game.state.observables.len(),
+            unobservables: game.state.unobservables.len(),
+            observed: game.state.observed.len(),
+            acquired: owned.iter().filter(|key| !owned_before.contains(*key)).cloned().collect()
+        });
+        owned_before = owned.into_iter().collect();
+    }
+    Ok(history)
+}
+
+// Telescopes, sites and accessories, in that order
+fn owned(game: &Game) -> Vec<String> {
+    game.state.telescopes.iter().map(|t| t.key.clone())
+        .chain(game.state.sites.iter().map(|site| site.key.clone()))
+        .chain(game.state.accessories.iter().map(|acc| acc.key.clone()))
+        .collect()
+}
+
+fn play(game: &mut Game, strategy: Strategy) {
+    match strategy {
+        Strategy::Random => game.random_observation(),
+        Strategy::Greedy => {
+            let deepest: Option<AstroObject> = game.state.observables.iter()
+                .max_by_key(|obj| obj.power_needed)
+                .cloned();
+            match deepest {
+                Some(obj) => game.observe(obj),
+                None => game.explore_somewhere()
+            }
+        }
+        Strategy::Idle => {}
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options> {
+    let mut options = Options {
+        generations: 100,
+        seed: Seed::from_entropy(),
+        strategy: Strategy::Random,
+        format: Format::Text,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().with_context(|| format!("{} needs a value\n{}", arg, USAGE));
+        match arg.as_str() {
+            "--generations" => options.generations = value()?.parse().context("--generations should be a whole number")?,
+            "--seed" => options.seed = Seed(value()?.parse().context("--seed should be a number")?),
+            "--strategy" => options.strategy = match value()?.as_str() {
+                "random" => Strategy::Random,
+                "greedy" => Strategy::Greedy,
+                "idle" => Strategy::Idle,
+                other => bail!("unknown strategy \"{}\"\n{}", other, USAGE),
+            },
+            "--format" => options.format = match value()?.as_str() {
+                "text" => Format::Text,
+                "csv" => Format::Csv,
+                "jsonl" => Format::JsonLines,
+                other => bail!("unknown format \"{}\"\n{}", other, USAGE),
+            },
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => bail!("unknown argument \"{}\"\n{}", other, USAGE),
+        }
+    }
+    Ok(options)
+}
+
+fn print_text(options: &Options, history: &[Stats]) {
+    println!("Simulated {} generations, seed {}, {:?} strategy", options.generations, options.seed.0, options.strategy);
+    println!();
+    println!("{:>10} {:>9} {:>15} {:>9} {:>11} {:>13} {:>8}  acquired", "generation", "max_power", "effective_power", "photons", "observables", "unobservables", "observed");
+    for stats in history {
+        println!(
+            "{:>10} {:>9} {:>15} {:>9} {:>11} {:>13} {:>8}  {}",
+            stats.generation, stats.max_power, stats.effective_power, stats.photons, stats.observables, stats.unobservables, stats.observed, stats.acquired.join(" ")
+        );
+    }
+
+    println!();
+    println!("Acquired:");
+    history.iter()
+        .flat_map(|stats| stats.acquired.iter().map(move |key| (stats.generation, key)))
+        .for_each(|(generation, key)| println!("  {:<16} at generation {}", key, generation));
+}
+
+fn print_csv(history: &[Stats]) {
+    println!("generation,max_power,effective_power,photons,observables,unobservables,observed,acquired");
+    for stats in history {
+        println!(
+            "{},{},{},{},{},{},{},{}",
+            stats.generation, stats.max_power, stats.effective_power, stats.photons, stats.observables, stats.unobservables, stats.observed, stats.acquired.join(";")
+        );
+    }
+}
+
+fn print_json_lines(history: &[Stats]) -> Result<()> {
+    for stats in history {
+        let line = serde_json::json!({
+            "generation": stats.generation,
+            "max_power": stats.max_power,
+            "effective_power": stats.effective_power,
+            "photons": stats.photons,
+            "observables": stats.observables,
+            "unobservables": stats.unobservables,
+            "observed": stats.observed,
+            "acquired": stats.acquired,
+        });
+        println!("{}", serde_json::to_string(&line)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(seed: u64, strategy: Strategy) -> Options {
+        Options { generations: 60, seed: Seed(seed), strategy, format: Format::Text }
+    }
+
+    #[test]
+    fn same_seed_same_run() {
+        for strategy in [Strategy::Random, Strategy::Greedy, Strategy::Idle] {
+            let history = simulate(&options(1234, strategy)).unwrap();
+            assert_eq!(history.len(), 60);
+            assert_eq!(history, simulate(&options(1234, strategy)).unwrap(), "{:?}", strategy);
+        }
+
+        // The strategies actually play differently
+        let observed = |strategy| simulate(&options(1234, strategy)).unwrap().last().unwrap().observed;
+        assert_eq!(observed(Strategy::Idle), 0);
+        assert!(observed(Strategy::Greedy) > 0);
+    }
+
+    #[test]
+    fn report_what_init_hands_out() {
+        let history = simulate(&options(1234, Strategy::Idle)).unwrap();
+        assert_eq!(history[0].generation, 0);
+        assert!(history[0].acquired.contains(&"eye".to_string()));
+        assert!(history[0].acquired.contains(&"backyard".to_string()));
+
+        // Everything's reported once, when it's acquired
+        let mut acquired: Vec<&String> = history.iter().flat_map(|stats| stats.acquired.iter()).collect();
+        let count = acquired.len();
+        acquired.sort();
+        acquired.dedup();
+        assert_eq!(acquired.len(), count);
+    }
+
+    #[test]
+    fn parse_options() {
+        let args = |line: &str| parse_args(line.split_whitespace().map(String::from));
+        let parsed = args("--generations 20 --seed 7 --strategy greedy --format csv").unwrap();
+        assert_eq!((parsed.generations, parsed.seed.0, parsed.strategy, parsed.format), (20, 7, Strategy::Greedy, Format::Csv));
+        assert!(args("--strategy lazy").is_err());
+        assert!(args("--seed").is_err());
+        assert!(args("--generations -5").is_err());
+    }
+}
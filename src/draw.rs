@@ -93,19 +93,19 @@ impl CanvasDrawParams {
 
     pub fn config_context(&self, ctx: &CanvasRenderingContext2d) {
         if let Some(fill) = &self.fill {
-            ctx.set_fill_style(&fill.into());
+            ctx.set_fill_style_str(fill);
         }
         if let Some(stroke) = &self.stroke {
-            ctx.set_stroke_style(&stroke.into());
+            ctx.set_stroke_style_str(stroke);
         }
         if let Some(line_cap) = &self.line_cap {
-            ctx.set_line_cap(&line_cap);
+            ctx.set_line_cap(line_cap);
         }
         if let Some(line_width) = self.line_width {
             ctx.set_line_width(line_width as f64);
         }
         if let Some(global_alpha) = self.global_alpha {
-            ctx.set_global_alpha(global_alpha as f64);
+            ctx.set_global_alpha(global_alpha);
         }
     }
 }
@@ -142,7 +142,7 @@ impl Draw {
         self.ctx.save();
         params.config_context(&self.ctx);
         self.ctx.begin_path();
-        draw_function(&self.ctx, &params);
+        draw_function(&self.ctx, params);
         if !params.do_not_close { self.ctx.close_path(); }
         if !params.do_not_fill { self.ctx.fill(); }
         if !params.do_not_stroke { self.ctx.stroke(); }
@@ -233,6 +233,21 @@ impl Draw {
         })
     }
 
+    /// Do some drawing, but only inside a circle
+    pub fn clipped_to_circle<F>(
+        &self,
+        center: Point,
+        radius: f64,
+        draw_function: F
+    ) where F: FnOnce() {
+        self.ctx.save();
+        self.ctx.begin_path();
+        self.ctx.arc(center.x, center.y, radius, 0.0, 2.0 * consts::PI).ok();
+        self.ctx.clip();
+        draw_function();
+        self.ctx.restore();
+    }
+
     pub fn arc(
         &self,
         center: Point,
@@ -309,12 +324,12 @@ impl Draw {
         }
         else {
             // let mut pos = pos.clone();
-            let offset = pos_loc.into_point(text_width as f64, text_height);
+            let offset = pos_loc.into_point(text_width, text_height);
             position += offset;
         }
 
         text_lines.iter().for_each(|text_line| {
-            self.ctx.fill_text(text_line, position.x as f64, position.y as f64).ok();
+            self.ctx.fill_text(text_line, position.x, position.y).ok();
             position += Point::xy(0.0, fontsize);
         });
 
@@ -371,7 +386,7 @@ impl Draw {
             let line = ctx.create_linear_gradient(0.0, 0.0, length, 0.0);
             line.add_color_stop(0.0, &color0).ok();
             line.add_color_stop(1.0, &color1).ok();
-            ctx.set_fill_style(&line);
+            ctx.set_fill_style_canvas_gradient(&line);
             ctx.fill_rect(0.0, -w / 2.0, length, w);
 
             // Put the context back how it was before
@@ -395,8 +410,8 @@ impl Draw {
     }
 
     /// Draw a smiley for testing :)
-    /// Example
-    /// ```
+    /// Example (not run as a doctest: `draw` is private, and it needs a canvas in a browser)
+    /// ```ignore
     /// let draw = Draw::from_canvas_id("game-canvas".to_string()).unwrap();
     /// draw.smiley(
     ///     Point::xy(200.0, 75.0),
@@ -410,18 +425,18 @@ impl Draw {
         head_size: f64,
         params: &CanvasDrawParams
     ) {
-        self.circle(head_position, head_size, &params);
+        self.circle(head_position, head_size, params);
 
         // Smile
-        self.arc(head_position, 0.7 * head_size, ANGLE_EAST, ANGLE_WEST, &params);
+        self.arc(head_position, 0.7 * head_size, ANGLE_EAST, ANGLE_WEST, params);
 
         // Eyes
         let eye_position_right = head_position + Point::xy(0.3,-0.2).scale(head_size);
         let eye_position_left = head_position + Point::xy(-0.3,-0.2).scale(head_size);
         let eye_position_center = head_position + Point::xy(0.0,-0.3).scale(head_size);
 
-        self.circle(eye_position_right, 0.1 * head_size, &params);
-        self.circle(eye_position_left, 0.1 * head_size, &params);
-        self.circle(eye_position_center, 0.1 * head_size, &params);
+        self.circle(eye_position_right, 0.1 * head_size, params);
+        self.circle(eye_position_left, 0.1 * head_size, params);
+        self.circle(eye_position_center, 0.1 * head_size, params);
     }
 }
\ No newline at end of file
@@ -1,224 +1,1119 @@
-use wasm_bindgen::prelude::*;
-use wasm_bindgen_futures::spawn_local;
-use wasm_bindgen::{JsCast};
-use gloo_timers::future::TimeoutFuture;
+// The game's rules and state. The front end (or the simulator) calls tick() and observe_at()
+
 use rand::prelude::*;
 use rand::rngs::SmallRng;
-use rand::distributions::WeightedIndex;
 use rand::seq::IteratorRandom;
-use super::draw::*;
-use super::points::Point;
-use web_sys::{EventTarget, MouseEvent};
-use gloo_events::*;
+use super::points::{Point, SkyCoord, SkyView, nearest_within};
 
+#[macro_use]
+pub mod platform;
 #[macro_use]
 mod types;
 mod data;
-use super::utils;
-
-use types::*;
-
-// Game settings
-const GAME_CANVAS_WIDTH: u32 = 800;
-const GAME_CANVAS_HEIGHT: u32 = 600;
-
-const GAME_CANVAS_ID: &str = "game-canvas";
-
-const COLOR_SKY: &str = "#08011a";
-const COLOR_GROUND_AT_DUSK: &str = "#24201a";
-
-const GAME_LOOP_MS: u32 = 1000;
-
-// const RANDOM_SEED: u64 = 29292929;
+mod loader;
+pub mod economy;
+pub mod away;
+pub mod save;
+pub mod seed;
+pub mod sky;
+pub mod weather;
+pub mod field;
+pub mod deep_field;
+pub mod category;
+pub mod events;
+pub mod journal;
 
-pub fn start() {
-    utils::set_panic_hook();
-    configure_canvas(GAME_CANVAS_ID.to_string(), GAME_CANVAS_WIDTH, GAME_CANVAS_HEIGHT);
-    let draw = Draw::from_canvas_id(GAME_CANVAS_ID.to_string()).unwrap();
-    // let mut rng = SmallRng::seed_from_u64(RANDOM_SEED);
-    let mut rng = SmallRng::from_entropy();
-    draw_background(&draw);
-    draw_stars(&draw, &mut rng, 800);
-    draw_ground(&draw);
+pub use types::*;
+use platform::{Platform, Logger, Clock, SilentLogger};
+use away::AwaySummary;
+use save::{SaveGame, Storage};
+use seed::Seed;
+use weather::Weather;
+use deep_field::Region;
+use events::EventKind;
 
-    let canvas = EventTarget::from(utils::query_html(&format!("#{}", GAME_CANVAS_ID)).unwrap());
-
-    let on_click = EventListener::new(&canvas, "click", move |event| {
-        let event = event.dyn_ref::<MouseEvent>().unwrap_throw();
-        let mouse_point = Point::xy(event.layer_x().into(), event.layer_y().into());
-        log!("Observing a spot in the sky: {} {}", mouse_point.x, mouse_point.y);
-    });
-
-    let cursor = utils::query_html("#eye-cursor").unwrap();
-
-    let move_cursor_icon = EventListener::new(&canvas, "mousemove", move |event| {
-        let event = event.dyn_ref::<MouseEvent>().unwrap_throw();
-        let mouse_point = Point::xy(event.layer_x().into(), event.layer_y().into());
-        let css = cursor.style();
-        css.set_property("top", &format!("{}px", mouse_point.y - 20.0)).ok();
-        css.set_property("left", &format!("{}px", mouse_point.x - 20.0)).ok();
-    });
-
-    // let these event listeners outlive this function
-    on_click.forget();
-    move_cursor_icon.forget();
-
-    let mut g = Game::new();
-    g.init();
-    g.main_loop();
+// How long a tick lasts in real time
+pub const TICK_MS: u32 = 1000;
 
+// How close (in px) a click has to be to an object to observe it.
+// A more powerful telescope makes it easier to pick things out.
+const HIT_RADIUS_BASE: f64 = 10.0;
+const HIT_RADIUS_PER_POWER: f64 = 1.5;
 
+// A telescope turning to point at something. It gets observed once it arrives
+#[derive(Debug, Clone)]
+pub struct Slew {
+    pub target: AstroObject,
+    pub arrives: i32 // the generation it gets there
 }
 
-struct Game {
+pub struct Game {
     pub state: GameState,
-    pub astro_objects: Vec<AstroObject>,
     pub telescopes: TelescopeIndex,
+    pub sites: SiteIndex,
+    pub accessories: AccessoryIndex,
+    pub event_kinds: Vec<EventKind>,
+    pub view: SkyView, // the part of the sky the player is looking at, in their own (alt/az) frame
+    pub pointing: SkyCoord, // where the telescope's pointed, in the same frame as the view
+    pub slew: Option<Slew>, // where it's turning to, if it's on the move
+    pub seed: Seed,
+    pub seeded: bool, // whether the player chose the seed, for a reproducible run
     pub rng: SmallRng,
-    pub generation: i32
+    pub generation: i32,
+    pub last_seen_ms: f64, // when the game last ran, by its clock (0 for a new game)
+    pub logger: Box<dyn Logger>,
+    pub clock: Box<dyn Clock>
 }
 
 impl Game {
-    pub fn new() -> Self {
+    pub fn new(seed: Seed, platform: Platform) -> Self {
+        let mut data = data::game_data();
         let mut game = Self {
             state: GameState::init(),
-            astro_objects: vec![],
-            telescopes: data::game_telescopes(),
-            rng: SmallRng::from_entropy(),
-            generation: 0
+            telescopes: data.telescopes,
+            sites: data.sites,
+            accessories: data.accessories,
+            event_kinds: data.events,
+            pointing: data.view.center,
+            slew: None,
+            view: data.view,
+            seed,
+            seeded: false,
+            rng: seed.stream(seed::OBSERVATIONS),
+            generation: 0,
+            last_seen_ms: 0.0,
+            logger: platform.logger,
+            clock: platform.clock
         };
-        let mut objects = data::game_objects();
-        game.state.add_data(&mut objects);
+        game.state.observer = data.observer;
+        game.state.weather = Weather::at(seed, 0, &game.state.observer);
+        game.state.add_data(&mut data.objects);
         game
     }
 
     pub fn init(&mut self) {
-        // Start with just your eye
+        // Start with just your eye, wherever the free site is
         self.state.add_telescope(self.telescopes.get_by_key("eye").unwrap());
+        self.acquire_sites();
     }
 
     #[cfg(test)]
     pub fn test(&mut self) {
         self.state.add_telescope(self.telescopes.get_by_key("eye").unwrap());
-        self.state.log();
+        self.state.log(&*self.logger);
         self.random_observation();
         self.random_observation();
         self.random_observation();
         self.random_observation();
-        self.state.log();
+        self.state.log(&*self.logger);
     }
 
-    pub fn main_loop(mut self) {
-        log!("Game generation: {}", self.generation);
+    /// One generation of the game
+    pub fn tick(&mut self) {
+        log_to!(self.logger, "Game generation: {}", self.generation);
+        self.advance(1);
+        self.state.log(&*self.logger);
+        self.last_seen_ms = self.clock.now_ms();
+    }
 
+    // Move the game on by some generations in one go. Photons come in at the same rate
+    // the whole time (it only changes when something's observed or acquired), so that's
+    // just the same as ticking; anything else (weather, events, slews) only gets
+    // looked at as of the last generation.
+    fn advance(&mut self, ticks: i32) {
+        let generation = self.generation + ticks;
+        self.state.observer.advance(ticks);
+        self.state.move_objects(generation);
+        self.update_events(generation);
+        self.state.weather = Weather::at(self.seed, generation, &self.state.observer);
+        self.state.refresh_observables();
+        self.generation = generation;
+        self.finish_slew();
+        self.state.collect_photons(ticks as u64);
         self.acquire_telescopes();
-        self.random_observation();
-        self.state.log();
+        self.acquire_sites();
+    }
+
+    /// Fast-forward through the ticks missed since the game last ran, counting at most
+    /// max_away_ms of them. While away, the player's telescope keeps working through
+    /// its observing queue. Returns None if no time has passed (or it's a new game).
+    pub fn catch_up(&mut self, max_away_ms: f64) -> Option<AwaySummary> {
+        let now_ms = self.clock.now_ms();
+        let away_ms = now_ms - self.last_seen_ms;
+        if self.last_seen_ms <= 0.0 || away_ms < TICK_MS as f64 {
+            return None;
+        }
+        let counted_ms = away_ms.min(max_away_ms);
+        let ticks = (counted_ms / TICK_MS as f64).floor() as i32;
+
+        let photons_before = self.state.photons_earned;
+        let observed_before = self.state.observed.clone();
+        let telescopes_before = self.state.telescopes.clone();
 
-        self.proceed();
+        // Rather than ticking thousands of times, move on an observation at a time.
+        // Nobody needs to read about each one, either
+        let logger = std::mem::replace(&mut self.logger, Box::new(SilentLogger));
+        let mut done = 0;
+        while done < ticks {
+            let batch = away::AWAY_TICKS_PER_OBSERVATION.min(ticks - done);
+            self.advance(batch);
+            done += batch;
+            if batch == away::AWAY_TICKS_PER_OBSERVATION {
+                self.random_observation();
+            }
+        }
+        self.logger = logger;
+        self.last_seen_ms = now_ms;
+
+        Some(AwaySummary {
+            away_ms,
+            counted_ms,
+            ticks,
+            photons: self.state.photons_earned - photons_before,
+            observations: self.state.observed.iter()
+                .filter(|(obj, detail_level)| observed_before.get(obj) != Some(detail_level))
+                .map(|(obj, _)| obj.name.clone())
+                .collect(),
+            telescopes: self.state.telescopes.difference(&telescopes_before)
+                .map(|scope| scope.name.clone())
+                .collect()
+        })
     }
 
-    pub fn proceed(mut self) {
-        self.generation += 1;
-        spawn_local(async {
-            TimeoutFuture::new(GAME_LOOP_MS).await;
-            self.main_loop();
+    /// Take a snapshot of the game. SmallRng can't be serialized, so this reseeds it
+    /// from a value that goes into the save; a restored game carries on exactly like this one.
+    pub fn save(&mut self) -> SaveGame {
+        let rng_seed = self.rng.gen();
+        self.rng = SmallRng::seed_from_u64(rng_seed);
+
+        let mut telescopes: Vec<String> = self.state.telescopes.iter().map(|t| t.key.clone()).collect();
+        telescopes.sort();
+        let mut sites: Vec<String> = self.state.sites.iter().map(|site| site.key.clone()).collect();
+        sites.sort();
+        let mut accessories: Vec<String> = self.state.accessories.iter().map(|acc| acc.key.clone()).collect();
+        accessories.sort();
+
+        SaveGame {
+            version: save::SAVE_VERSION,
+            generation: self.generation,
+            seed: self.seed.0,
+            rng_seed,
+            saved_at_ms: self.clock.now_ms(),
+            telescopes,
+            sites,
+            accessories,
+            active: self.state.active.clone(),
+            observed: self.state.observed.iter().map(|(obj, detail_level)| (obj.key.clone(), *detail_level)).collect(),
+            explored: self.state.explored.iter().map(|(region, depths)| (region.key(), *depths)).collect(),
+            journal: self.state.journal.clone(),
+            pointing: Some((self.pointing.ra, self.pointing.dec)),
+            slew: self.slew.as_ref().map(|slew| save::SavedSlew { target: slew.target.key.clone(), arrives: slew.arrives }),
+            photons: self.state.photons,
+            photons_earned: self.state.photons_earned
+        }
+    }
+
+    /// Rebuild a game from a snapshot. Anything that's no longer in the game data is skipped.
+    pub fn restore(save: SaveGame, platform: Platform) -> Self {
+        let mut game = Self::new(Seed(save.seed), platform);
+        game.generation = save.generation;
+        game.last_seen_ms = save.saved_at_ms;
+        game.rng = SmallRng::seed_from_u64(save.rng_seed);
+        game.state.photons = save.photons;
+        game.state.photons_earned = save.photons_earned;
+        game.state.journal = save.journal;
+        game.state.observer.advance(save.generation);
+        game.state.weather = Weather::at(game.seed, save.generation, &game.state.observer);
+
+        for key in save.telescopes.iter() {
+            if let Some(scope) = game.telescopes.get_by_key(key) {
+                game.state.add_telescope(scope);
+            }
+        }
+
+        if let Some(key) = save.active {
+            game.state.use_telescope(&key).ok();
+        }
+
+        for key in save.sites.iter() {
+            if let Some(site) = game.sites.get_by_key(key) {
+                game.state.add_site(site);
+            }
+        }
+        if game.state.sites.is_empty() {
+            game.acquire_sites();
+        }
+
+        for key in save.accessories.iter() {
+            if let Some(acc) = game.accessories.get_by_key(key) {
+                game.state.add_accessory(acc);
+            }
+        }
+
+        // So do the events that were going on
+        game.update_events(save.generation);
+
+        // The deep field comes out the same from the seed, so it only needs regenerating
+        for (key, depths) in save.explored.iter() {
+            if let Ok(region) = key.parse::<Region>() {
+                game.explore_region(region, *depths);
+            }
+        }
+
+        // Events that are over aren't in the sky any more, but they still count
+        for (key, detail_level) in save.observed {
+            let obj = game.state.take_unobserved(&key).or_else(|| {
+                events::event_for_key(game.seed, &game.event_kinds, &key).map(|event| event.object_at(event.end))
+            });
+            if let Some(obj) = obj {
+                let detail_level = std::cmp::min(detail_level, obj.detail.len().saturating_sub(1));
+                game.state.observed.insert(obj, detail_level);
+            }
+        }
+        game.state.move_objects(save.generation);
+        game.state.refresh_observables();
+
+        // The telescope carries on turning to whatever it was turning to
+        if let Some((ra, dec)) = save.pointing {
+            game.pointing = SkyCoord::radians(ra, dec);
+        }
+        game.slew = save.slew.and_then(|slew| {
+            let target = game.state.find(&slew.target)?.clone();
+            Some(Slew { target, arrives: slew.arrives })
         });
+
+        game
+    }
+
+    pub fn save_to(&mut self, storage: &mut dyn Storage) -> anyhow::Result<()> {
+        storage.store(save::SAVE_KEY, &self.save().to_json()?)
     }
 
+    /// Restore the game from storage, if there's a saved game in there
+    pub fn load_from(storage: &dyn Storage, platform: Platform) -> anyhow::Result<Option<Self>> {
+        match storage.load(save::SAVE_KEY)? {
+            Some(json) => Ok(Some(Self::restore(SaveGame::from_json(&json)?, platform))),
+            None => Ok(None)
+        }
+    }
+
+    // Pick up the next telescopes in the progression, if the player has earned them
+    // (and they're free; otherwise they have to be bought)
     pub fn acquire_telescopes(&mut self) {
-        // todo
+        while let Some(scope) = self.state.next_telescope(&self.telescopes) {
+            if !scope.is_unlocked(&self.state) || scope.price > 0 {
+                break;
+            }
+            log_to!(self.logger, "> You got a new telescope: {}! {}", scope.name, scope.description);
+            self.state.add_telescope(scope);
+        }
+    }
+
+    /// The next telescope in the progression, if it's unlocked and up for sale
+    pub fn telescope_for_sale(&self) -> Option<Telescope> {
+        self.state.next_telescope(&self.telescopes)
+            .filter(|scope| scope.price > 0 && scope.is_unlocked(&self.state))
+    }
+
+    pub fn buy_telescope(&mut self, key: &str) -> anyhow::Result<()> {
+        let scope = self.telescopes.get_by_key(key)
+            .ok_or_else(|| anyhow::anyhow!("There's no such telescope as \"{}\"", key))?;
+        self.state.buy_telescope(scope.clone())?;
+        log_to!(self.logger, "> You bought a new telescope: {}! {}", scope.name, scope.description);
+        Ok(())
+    }
+
+    /// Switch to another of the player's telescopes. Whatever the old one was turning to is forgotten
+    pub fn use_telescope(&mut self, key: &str) -> anyhow::Result<()> {
+        self.state.use_telescope(key)?;
+        self.slew = None;
+        if let Some(scope) = self.state.telescope() {
+            log_to!(self.logger, "> You're observing with {} now.", scope.name);
+        }
+        Ok(())
+    }
+
+    // Like telescopes, free sites get picked up as soon as they're unlocked
+    pub fn acquire_sites(&mut self) {
+        while let Some(site) = self.state.next_site(&self.sites) {
+            if !site.is_unlocked(&self.state) || site.price > 0 {
+                break;
+            }
+            log_to!(self.logger, "> You set up your telescopes at a new site: {}. {}", site.name, site.description);
+            self.state.add_site(site);
+        }
+    }
+
+    /// The next site in the progression, if it's unlocked and up for sale
+    pub fn site_for_sale(&self) -> Option<Site> {
+        self.state.next_site(&self.sites)
+            .filter(|site| site.price > 0 && site.is_unlocked(&self.state))
+    }
+
+    pub fn buy_site(&mut self, key: &str) -> anyhow::Result<()> {
+        let site = self.sites.get_by_key(key)
+            .ok_or_else(|| anyhow::anyhow!("There's no such site as \"{}\"", key))?;
+        self.state.buy_site(site.clone())?;
+        log_to!(self.logger, "> You moved your telescopes to {}! {}", site.name, site.description);
+        Ok(())
+    }
+
+    /// Accessories the player could buy now: unlocked, and fitting one of their telescopes
+    pub fn accessories_for_sale(&self) -> Vec<Accessory> {
+        let mut for_sale: Vec<Accessory> = self.accessories.values()
+            .filter(|acc| !self.state.accessories.contains(*acc) && acc.is_unlocked(&self.state))
+            .filter(|acc| self.state.telescopes.iter().any(|scope| acc.fits(scope)))
+            .cloned()
+            .collect();
+        for_sale.sort_by_key(|acc| acc.tier);
+        for_sale
+    }
+
+    pub fn buy_accessory(&mut self, key: &str) -> anyhow::Result<()> {
+        let acc = self.accessories.get_by_key(key)
+            .ok_or_else(|| anyhow::anyhow!("There's no such accessory as \"{}\"", key))?;
+        self.state.buy_accessory(acc.clone())?;
+        log_to!(self.logger, "> You bought {}! {}", acc.name, acc.description);
+        Ok(())
+    }
+
+    // Bring in the events that have started, update the ones that are going on,
+    // and clear away the ones that are over
+    fn update_events(&mut self, generation: i32) {
+        let over: Vec<String> = self.state.events.values()
+            .filter(|event| event.is_over(generation))
+            .map(|event| event.key.clone())
+            .collect();
+        for key in over {
+            if let Some(event) = self.state.events.get(&key) {
+                log_to!(self.logger, "> {} is gone.", event.object_at(generation).name);
+            }
+            self.state.expire(&key);
+        }
+
+        for event in events::events_at(self.seed, &self.event_kinds, generation) {
+            let obj = event.object_at(generation);
+            if self.state.events.contains_key(&event.key) {
+                self.state.update_object(obj);
+                continue;
+            }
+            log_to!(self.logger, "> Something new has appeared in the sky: {}! Catch it before it's gone.", obj.name);
+            self.state.add_data(&mut vec![obj]);
+            self.state.events.insert(event.key.clone(), event);
+        }
+    }
+
+    /// Generate the deep field where the player's looking, as deep as their best telescope
+    /// can see (and a layer beyond that, to aim for)
+    pub fn explore(&mut self, coords: SkyCoord) {
+        self.explore_region(Region::containing(coords), deep_field::depths_for_power(self.state.max_power));
+    }
+
+    /// Explore a random region of the sky that's above the horizon
+    pub fn explore_somewhere(&mut self) {
+        let observer = self.state.observer;
+        if let Some(region) = Region::all().filter(|region| observer.is_up(region.center())).choose(&mut self.rng) {
+            self.explore(region.center());
+        }
+    }
+
+    // Generate the layers of a region that haven't been yet, down to some depth
+    fn explore_region(&mut self, region: Region, depths: u32) {
+        let explored = self.state.explored.get(&region).copied().unwrap_or(0);
+        if depths <= explored {
+            return;
+        }
+        for depth in explored..depths {
+            self.state.add_data(&mut deep_field::generate(self.seed, region, depth));
+        }
+        self.state.explored.insert(region, depths);
+    }
+
+    /// Where an object appears on the canvas, if it's in view and above the horizon
+    pub fn position_of(&self, obj: &AstroObject) -> Option<Point> {
+        if !self.state.observer.is_up(obj.coords) {
+            return None;
+        }
+        self.sky_position(obj.coords)
+    }
+
+    /// Where a point in the sky appears on the canvas right now, if it's in view.
+    /// (It could be below the horizon.)
+    pub fn sky_position(&self, coords: SkyCoord) -> Option<Point> {
+        self.view.project(self.state.observer.alt_az(coords))
+    }
+
+    pub fn hit_radius(&self) -> f64 {
+        let power = self.state.telescope().map_or(0, |scope| scope.max_power);
+        HIT_RADIUS_BASE + HIT_RADIUS_PER_POWER * power as f64
+    }
+
+    // Observe whatever the player clicked on, if they were close enough to something.
+    // The telescope has to turn to it first, and big ones take a while.
+    pub fn observe_at(&mut self, point: Point) {
+        if let Some(slew) = &self.slew {
+            log_to!(self.logger, "> Your telescope is still turning toward {}.", slew.target.name);
+            return;
+        }
+        if let Some(horizontal) = self.view.unproject(point) {
+            self.explore(self.state.observer.ra_dec(horizontal));
+        }
+        let nearest = nearest_within(
+            point,
+            self.hit_radius(),
+            self.state.observables.iter().filter_map(|obj| Some((self.position_of(obj)?, obj)))
+        ).cloned();
+
+        match nearest {
+            Some(obj) => self.slew_to(obj),
+            None => log_to!(self.logger, "> You can't make out anything there.")
+        }
+    }
+
+    // Point the telescope at an object, and observe it as soon as it gets there
+    fn slew_to(&mut self, obj: AstroObject) {
+        let slew_speed = self.state.telescope().map_or(f64::INFINITY, |scope| scope.slew_speed);
+        let target = self.state.observer.alt_az(obj.coords);
+        let ticks = (self.pointing.separation(target) / slew_speed).floor() as i32;
+        self.pointing = target;
+
+        if ticks == 0 {
+            self.observe(obj);
+        }
+        else {
+            log_to!(self.logger, "> Turning your telescope toward {}...", obj.name);
+            self.slew = Some(Slew { target: obj, arrives: self.generation + ticks });
+        }
+    }
+
+    // Observe whatever the telescope's been turning toward, once it's there.
+    // The sky keeps turning while it does, so the telescope follows its target
+    fn finish_slew(&mut self) {
+        match &self.slew {
+            Some(slew) if slew.arrives <= self.generation => {}
+            _ => return
+        }
+        let slew = match self.slew.take() {
+            Some(slew) => slew,
+            None => return
+        };
+        // It's had time to move, or fade, or be gone altogether since the player clicked
+        match self.state.find(&slew.target.key).filter(|obj| !self.state.is_gone(obj)).cloned() {
+            Some(target) => {
+                self.pointing = self.state.observer.alt_az(target.coords);
+                self.observe(target);
+            }
+            None => log_to!(self.logger, "> By the time your telescope got there, {} was gone.", slew.target.name)
+        }
     }
 
     // Make a random observation.
     pub fn random_observation(&mut self) {
-        // choose a random observable
-        if self.state.observables.len() == 0 {
-            log!("> There's nothing I can observe right now.");
+        // choose a random observable, or go looking for one
+        if self.state.observables.is_empty() {
+            self.explore_somewhere();
+        }
+        if self.state.observables.is_empty() {
+            log_to!(self.logger, "> There's nothing I can observe right now.");
             return;
         }
         let obj = self.state.observables.iter().choose(&mut self.rng).unwrap().clone();
+        self.observe(obj);
+    }
+
+    pub fn observe(&mut self, obj: AstroObject) {
+        let best_detail = match self.state.best_detail(&obj) {
+            Some(detail) => detail.clone(),
+            None => {
+                log_to!(self.logger, "> {} is too faint to make out with your telescopes.", obj.name);
+                return;
+            }
+        };
+        if !self.state.observer.is_up(obj.coords) {
+            log_to!(self.logger, "> {} is below the horizon right now.", obj.name);
+            return;
+        }
+        let detail_level = best_detail.level;
         self.state.observables.remove(&obj);
-        let detail_level = obj.detail.iter().fold(0, |level, next_detail| {
-            if next_detail.power_needed <= self.state.max_power {
-                next_detail.level
+
+        let discoveries = match self.state.observed.get(&obj).copied() {
+            None => {
+                log_to!(self.logger, "> You are observing {}. {}", obj.name, best_detail.discovery_text);
+                if let Some(event) = self.state.events.get(&obj.key) {
+                    let bonus = event.bonus;
+                    log_to!(self.logger, "> You caught it in time! That's worth {} photons.", bonus);
+                    self.state.photons = self.state.photons.saturating_add(bonus);
+                    self.state.photons_earned = self.state.photons_earned.saturating_add(bonus);
+                }
+                vec![best_detail.discovery_text]
             }
-            else {
-                level
+            Some(previous_level) if previous_level >= detail_level => {
+                log_to!(self.logger, "> You are observing {} again, but there's nothing new to see.", obj.name);
+                self.log_phase(&obj);
+                return;
             }
-        });
-        log!("> You are observing {}. {}", obj.name, obj.detail[detail_level].discovery_text);
+            Some(previous_level) => {
+                // Reveal each level of detail that's new since last time
+                obj.remaining_levels(previous_level).iter()
+                    .take_while(|d| d.level <= detail_level)
+                    .map(|detail| {
+                        log_to!(self.logger, "> You are observing {} again. {}", obj.name, detail.discovery_text);
+                        detail.discovery_text.clone()
+                    })
+                    .collect()
+            }
+        };
+        self.log_phase(&obj);
+        self.write_up(&obj, detail_level, discoveries);
         self.state.observed.insert(obj, detail_level);
     }
-}
 
-fn configure_canvas(canvas_id: String, width: u32, height: u32) {
-    let canvas = utils::get_canvas_by_id(canvas_id).unwrap();
-    canvas.set_width(width);
-    canvas.set_height(height);
-}
+    // Put an observation in the journal, along with how the night was
+    fn write_up(&mut self, obj: &AstroObject, level: usize, discoveries: Vec<String>) {
+        let entry = journal::Entry {
+            generation: self.generation,
+            day: self.state.observer.day,
+            object: obj.key.clone(),
+            name: obj.name.clone(),
+            category: obj.category.behavior().key().to_string(),
+            level,
+            discoveries,
+            telescope: self.state.telescope().map_or_else(String::new, |scope| scope.key.clone()),
+            site: self.state.site().map(|site| site.key.clone()),
+            power: self.state.effective_power(),
+            weather: self.state.weather
+        };
+        self.state.journal.record(entry);
+    }
 
-fn draw_background(draw: &Draw) {
-    draw.fill_all(COLOR_SKY.to_string());
+    // The Moon and Venus look different from night to night
+    fn log_phase(&self, obj: &AstroObject) {
+        if let Some(phase) = obj.motion.and_then(|motion| motion.phase_at(self.generation)) {
+            log_to!(self.logger, "> Right now {} is {}% lit ({}).", obj.name, (phase.illuminated * 100.0).round(), phase);
+        }
+    }
 }
 
-#[allow(clippy::many_single_char_names)]
-fn draw_stars(draw: &Draw, mut rng: &mut SmallRng, n: usize) {
-    // star magnitude distributions
-    let star_mag_buckets = [
-        (1.0, 0.262),
-        (1.2, 0.194),
-        (1.4, 0.144),
-        (1.6, 0.106),
-        (1.8, 0.079),
-        (2.0, 0.058),
-        (2.2, 0.043),
-        (2.4, 0.032),
-        (2.6, 0.024),
-        (2.8, 0.018),
-        (3.0, 0.013),
-        // (3.2, 0.010),
-        // (3.4, 0.007),
-        // (3.6, 0.005),
-        // (3.8, 0.004),
-        // (4.0, 0.003),
-    ];
-    let star_color_buckets = [
-        ("#d6f0ff", 1),
-        ("#e0e8ff", 2),
-        ("#f8f7ff", 4),
-        ("#fff4ea", 2),
-        ("#ffe9d2", 1),
-    ];
-    let star_mag_dist = WeightedIndex::new(star_mag_buckets.iter().map(|star| star.1)).unwrap();
-    let star_color_dist = WeightedIndex::new(star_color_buckets.iter().map(|star| star.1)).unwrap();
-    let mut params = CanvasDrawParams::new().fill(common_colors::WHITE.to_string()).do_not_stroke(false);
-    let star_mags: Vec<f64> = star_mag_dist.sample_iter(&mut rng).take(n).map(|i| star_mag_buckets[i].0).collect();
-    let star_colors: Vec<&str> = star_color_dist.sample_iter(&mut rng).take(n).map(|i| star_color_buckets[i].0).collect();
-    for i in 0..n {
-        // Draw a star!
-        let x: i64 = rng.gen_range(0..GAME_CANVAS_WIDTH).into();
-        let y: i64 = rng.gen_range(0..GAME_CANVAS_HEIGHT).into();
-        let r: f64 = star_mags[i];
-        let a: f64 = rng.gen_range(0.3..1.0);
-        let should_stroke = r >= 2.0; // adding an outline looks nice only on the larger stars
-        params = params.fill(star_colors[i].to_string()).do_not_stroke(should_stroke).global_alpha(a);
-        // log!("star {} {} {}", i, r, star_colors[i]);
-        draw.circle(Point::xy(x as f64, y as f64), r, &params);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::f64::consts;
+    use crate::orbits::{self, Body};
+    use platform::ManualClock;
+    use save::MemoryStorage;
+
+    // A game that starts on a clear night, until it ticks
+    fn new_game(seed: u64) -> Game {
+        let mut game = Game::new(Seed(seed), Platform::headless());
+        game.state.weather = Weather::clear();
+        game.init();
+        game
     }
-}
 
-fn draw_ground(draw: &Draw) {
-    let params = CanvasDrawParams::new().fill(COLOR_GROUND_AT_DUSK.to_string());
-    draw.rect(
-        Point::xy(0.0, GAME_CANVAS_HEIGHT as f64),
-        Point::xy(GAME_CANVAS_WIDTH as f64, (GAME_CANVAS_HEIGHT - 50) as f64),
-        &params
-    );
-}
+    fn game_object(key: &str) -> AstroObject {
+        data::game_data().objects.into_iter().find(|obj| obj.key == key).unwrap()
+    }
+
+    fn observed_keys(game: &Game) -> Vec<(String, usize)> {
+        game.state.observed.iter().map(|(obj, level)| (obj.key.clone(), *level)).collect()
+    }
+
+    #[test]
+    fn runs_without_a_browser() {
+        let mut game = Game::new(Seed(1), Platform::headless());
+        game.state.weather = Weather::clear();
+        game.test();
+        // With just your eye, you can see Sirius, Andromeda, and the planets that are up
+        let observed = vec![("jupiter", 0), ("m31", 0), ("saturn", 0), ("sirius", 0)];
+        assert_eq!(observed_keys(&game), observed.into_iter().map(|(key, level)| (key.to_string(), level)).collect::<Vec<_>>());
+        // Venus is up too, but that's all the looking the test does
+        let left: Vec<&str> = game.state.observables.iter().map(|obj| obj.key.as_str()).collect();
+        assert_eq!(left, vec!["venus"]);
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let play = |seed| {
+            let mut game = new_game(seed);
+            let mut order = vec![];
+            for _ in 0..5 {
+                game.random_observation();
+                game.tick();
+                order.push(observed_keys(&game));
+            }
+            (order, game.state.max_power)
+        };
+        assert_eq!(play(29292929), play(29292929));
+    }
+
+    #[test]
+    fn save_and_restore() {
+        let clock = Rc::new(ManualClock::new(1000.0));
+        let mut game = Game::new(Seed(7), Platform::new(Box::new(platform::SilentLogger), Box::new(clock.clone())));
+        game.init();
+        game.random_observation();
+        game.tick();
+
+        let mut storage = MemoryStorage::new();
+        clock.advance(500.0);
+        game.save_to(&mut storage).unwrap();
+
+        let mut restored = Game::load_from(&storage, Platform::headless()).unwrap().unwrap();
+        assert_eq!(restored.generation, game.generation);
+        assert_eq!(observed_keys(&restored), observed_keys(&game));
+        assert_eq!(restored.state.max_power, game.state.max_power);
+        assert_eq!(restored.state.journal, game.state.journal);
+        assert_eq!(SaveGame::from_json(&storage.load(save::SAVE_KEY).unwrap().unwrap()).unwrap().saved_at_ms, 1500.0);
+
+        // Both games carry on the same way
+        game.random_observation();
+        restored.random_observation();
+        assert_eq!(observed_keys(&restored), observed_keys(&game));
+    }
+
+    #[test]
+    fn observe_again_with_a_better_telescope() {
+        let mut game = new_game(1);
+        let sirius = || game_object("sirius");
+
+        // The naked eye can only see Sirius A
+        game.observe(sirius());
+        assert_eq!(game.state.observed.get(&sirius()), Some(&0));
+        assert!(!game.state.observables.contains(&sirius()));
+
+        // A telescope, under a dark enough sky, brings it back for another look...
+        game.state.add_telescope(game.telescopes.get_by_key("refractor_2in").unwrap());
+        assert!(!game.state.observables.contains(&sirius()));
+        game.state.add_site(game.sites.get_by_key("dark_sky_park").unwrap());
+        assert!(game.state.observables.contains(&sirius()));
+
+        // ...which reveals Sirius B
+        game.observe(sirius());
+        assert_eq!(game.state.observed.get(&sirius()), Some(&1));
+        assert_eq!(game.state.observed.len(), 1);
+
+        // There's nothing more to see, even with a bigger telescope
+        assert!(!game.state.observables.contains(&sirius()));
+        game.state.add_telescope(game.telescopes.get_by_key("keck").unwrap());
+        assert!(!game.state.observables.contains(&sirius()));
+    }
+
+    #[test]
+    fn keep_a_journal() {
+        let mut game = new_game(1);
+        let sirius = || game_object("sirius");
+        game.observe(sirius());
+        game.observe(sirius());
+        game.state.add_telescope(game.telescopes.get_by_key("refractor_2in").unwrap());
+        game.state.add_site(game.sites.get_by_key("dark_sky_park").unwrap());
+        for _ in 0..10 {
+            game.tick();
+        }
+        game.state.weather = Weather::clear();
+        game.observe(sirius());
+
+        // Looking again without seeing anything new doesn't count
+        let journal = &game.state.journal;
+        let entries: Vec<(usize, &str, i32)> = journal.for_object("sirius")
+            .map(|entry| (entry.level, entry.telescope.as_str(), entry.generation))
+            .collect();
+        assert_eq!(entries, vec![(0, "eye", 0), (1, "refractor_2in", 10)]);
+        assert_eq!(journal.for_category(&AstroObjectCategory::Star).count(), 2);
+        assert_eq!(journal.with_telescope("refractor_2in").count(), 1);
+
+        // What was seen, and when and where it was seen from
+        let latest = journal.entries().last().unwrap();
+        assert_eq!(latest.discoveries, vec![sirius().detail[1].discovery_text.clone()]);
+        assert_eq!(latest.site.as_deref(), Some("dark_sky_park"));
+        assert!((latest.day - game.state.observer.day).abs() < 1e-9);
+        assert!(journal.export().contains("Sirius B"));
+    }
+
+    #[test]
+    fn light_pollution_limits_power() {
+        let mut game = new_game(1);
+        assert_eq!(game.state.site().unwrap().key, "backyard");
+
+        // Even the Keck can't see much from the city...
+        game.state.add_telescope(game.telescopes.get_by_key("keck").unwrap());
+        assert_eq!(game.state.effective_power(), game.state.site().unwrap().power_limit());
+        assert!(game.state.effective_power() < game.state.max_power);
+
+        // ...but it can from a dark site, once the player's earned one
+        assert_eq!(game.site_for_sale(), None);
+        assert!(game.buy_site("dark_sky_park").is_err());
+        game.state.observed.extend(data::game_data().objects.into_iter().map(|obj| (obj, 0)));
+        game.state.photons = 100;
+        assert_eq!(game.site_for_sale().map(|site| site.key), Some("dark_sky_park".to_string()));
+        assert!(game.buy_site("dark_sky_park").is_err());
+        game.state.photons = 1000;
+        game.buy_site("dark_sky_park").unwrap();
+        assert_eq!(game.state.photons, 850);
+        assert_eq!(game.state.site().unwrap().key, "dark_sky_park");
+        assert_eq!(game.state.effective_power(), 16);
+
+        // The player stays there
+        let mut storage = MemoryStorage::new();
+        game.save_to(&mut storage).unwrap();
+        let restored = Game::load_from(&storage, Platform::headless()).unwrap().unwrap();
+        assert_eq!(restored.state.site().unwrap().key, "dark_sky_park");
+    }
+
+    #[test]
+    fn accessories_have_to_fit() {
+        let mut game = new_game(1);
+        game.state.add_telescope(game.telescopes.get_by_key("refractor_2in").unwrap());
+        game.state.observed.extend(data::game_data().objects.into_iter().map(|obj| (obj, 2)));
+        game.state.photons = 10000;
+
+        // A camera won't go on a little refractor...
+        assert!(game.accessories_for_sale().iter().all(|acc| acc.key != "ccd_camera"));
+        assert!(game.buy_accessory("ccd_camera").is_err());
+
+        // ...but an eyepiece will
+        game.buy_accessory("plossl_10mm").unwrap();
+        assert_eq!(game.state.effective_power(), game.state.site().unwrap().power_limit());
+        assert!(game.buy_accessory("plossl_10mm").is_err());
+    }
+
+    #[test]
+    fn filters_reveal_more_detail() {
+        let mut game = new_game(1);
+        game.state.add_site(game.sites.get_by_key("dark_sky_park").unwrap());
+        game.state.add_telescope(game.telescopes.get_by_key("reflector_6in").unwrap());
+        let m42 = game_object("m42");
+        assert_eq!(game.state.best_detail(&m42).map(|detail| detail.level), Some(1));
+
+        // The nebula's clouds only show up through an H-alpha filter
+        game.state.add_accessory(game.accessories.get_by_key("h_alpha_filter").unwrap());
+        assert_eq!(game.state.best_detail(&m42).map(|detail| detail.level), Some(2));
+
+        // And they survive a save
+        let mut storage = MemoryStorage::new();
+        game.save_to(&mut storage).unwrap();
+        let restored = Game::load_from(&storage, Platform::headless()).unwrap().unwrap();
+        assert_eq!(restored.state.accessories, game.state.accessories);
+    }
+
+    #[test]
+    fn switch_telescopes() {
+        let mut game = new_game(1);
+        assert_eq!(game.state.active.as_deref(), Some("eye"));
+
+        // A new telescope gets used straight away...
+        game.state.add_telescope(game.telescopes.get_by_key("refractor_2in").unwrap());
+        assert_eq!(game.state.telescope().unwrap().key, "refractor_2in");
+        assert!(game.state.field_of_view().unwrap() < game.telescopes.get_by_key("eye").unwrap().fov);
+
+        // ...but the player can go back to their eye, and only the telescope in use counts
+        game.use_telescope("eye").unwrap();
+        assert_eq!(game.state.effective_power(), 4);
+        assert!(game.use_telescope("keck").is_err());
+        assert_eq!(game.state.active.as_deref(), Some("eye"));
 
-#[wasm_bindgen]
-extern {
-    fn alert(s: &str);
-    fn refresh(v: Vec<i32>);
+        let mut storage = MemoryStorage::new();
+        game.save_to(&mut storage).unwrap();
+        let restored = Game::load_from(&storage, Platform::headless()).unwrap().unwrap();
+        assert_eq!(restored.state.active.as_deref(), Some("eye"));
+    }
+
+    #[test]
+    fn big_telescopes_take_time_to_turn() {
+        let mut game = new_game(1);
+        let (sirius, m31) = (game_object("sirius"), game_object("m31"));
+
+        // The eye looks wherever it wants
+        game.observe_at(game.position_of(&sirius).unwrap());
+        assert!(game.state.observed.contains_key(&sirius));
+
+        // The Keck has to slew over to Andromeda, and can't be pointed anywhere else until it's there
+        game.state.add_telescope(game.telescopes.get_by_key("keck").unwrap());
+        game.observe_at(game.position_of(&m31).unwrap());
+        assert!(!game.state.observed.contains_key(&m31));
+        game.observe_at(game.position_of(&sirius).unwrap());
+        assert_eq!(game.slew.as_ref().unwrap().target, m31);
+        let arrives = game.slew.as_ref().unwrap().arrives;
+        assert!(arrives > game.generation + 1);
+
+        // Reloading partway there doesn't lose track of it
+        game.tick();
+        let mut storage = MemoryStorage::new();
+        game.save_to(&mut storage).unwrap();
+        let mut restored = Game::load_from(&storage, Platform::headless()).unwrap().unwrap();
+        assert_eq!(restored.pointing, game.pointing);
+        assert_eq!(restored.slew.as_ref().map(|slew| (&slew.target, slew.arrives)), Some((&m31, arrives)));
+
+        // It gets observed on the tick the telescope arrives
+        while restored.slew.is_some() {
+            assert!(!restored.state.observed.contains_key(&m31));
+            restored.tick();
+        }
+        assert!(restored.state.observed.contains_key(&m31));
+        assert_eq!(restored.generation, arrives);
+    }
+
+    #[test]
+    fn slew_to_something_that_goes() {
+        let mut game = new_game(1);
+        let event = (0..)
+            .flat_map(|generation| events::events_at(game.seed, &game.event_kinds, generation))
+            .find(|event| event.key.starts_with("perseids"))
+            .unwrap();
+        game.generation = event.end - 1;
+        game.update_events(event.end - 1);
+        let shower = game.state.observables.iter().find(|obj| obj.key == event.key).cloned().unwrap();
+
+        // It's over before the telescope gets there, so there's nothing to see
+        game.slew = Some(Slew { target: shower.clone(), arrives: event.end + 2 });
+        while game.slew.is_some() {
+            game.tick();
+        }
+        assert!(!game.state.observed.contains_key(&shower));
+        assert!(game.state.journal.is_empty());
+    }
+
+    #[test]
+    fn explore_the_deep_field() {
+        let mut game = new_game(1);
+        game.state.add_site(game.sites.get_by_key("dark_sky_park").unwrap());
+        game.state.add_telescope(game.telescopes.get_by_key("reflector_6in").unwrap());
+        let objects = game.state.observables.len() + game.state.unobservables.len();
+
+        // Overhead, there are faint galaxies to find
+        let zenith = game.state.observer.ra_dec(sky::horizontal(consts::FRAC_PI_2, 0.0));
+        game.explore(zenith);
+        assert!(game.state.observables.len() + game.state.unobservables.len() > objects);
+        let found = game.state.observables.iter()
+            .find(|obj| obj.key.starts_with(deep_field::KEY_PREFIX))
+            .cloned()
+            .expect("nothing in the deep field to see");
+        game.observe(found.clone());
+
+        // Exploring again doesn't make any more
+        let objects = game.state.observables.len() + game.state.unobservables.len();
+        game.explore(zenith);
+        assert_eq!(game.state.observables.len() + game.state.unobservables.len(), objects);
+
+        let mut storage = MemoryStorage::new();
+        game.save_to(&mut storage).unwrap();
+        let restored = Game::load_from(&storage, Platform::headless()).unwrap().unwrap();
+        assert_eq!(restored.state.explored, game.state.explored);
+        assert_eq!(restored.state.observed.get(&found), game.state.observed.get(&found));
+        let restored_found = restored.state.observed.keys().find(|obj| **obj == found).unwrap();
+        assert_eq!(restored_found.name, found.name);
+    }
+
+    #[test]
+    fn catch_a_meteor_shower() {
+        let mut game = new_game(1);
+        let event = (0..)
+            .flat_map(|generation| events::events_at(game.seed, &game.event_kinds, generation))
+            .find(|event| event.key.starts_with("perseids"))
+            .unwrap();
+
+        // It shows up, and it's worth a bonus to see it while it's there
+        game.generation = event.start;
+        game.update_events(event.start);
+        let shower = game.state.observables.iter().find(|obj| obj.key == event.key).cloned().unwrap();
+        let photons = game.state.photons;
+        game.observe(shower.clone());
+        assert_eq!(game.state.photons, photons + event.bonus);
+
+        let mut storage = MemoryStorage::new();
+        game.save_to(&mut storage).unwrap();
+        let mut restored = Game::load_from(&storage, Platform::headless()).unwrap().unwrap();
+        assert!(restored.state.events.contains_key(&event.key));
+        assert!(restored.state.observed.contains_key(&shower));
+
+        // Then it's gone from the sky, but it still counts as observed
+        let unlock = Unlock::ObjectsObserved(restored.state.observed.len());
+        let detail_levels = restored.state.detail_levels_reached();
+        restored.update_events(event.end);
+        assert!(restored.state.events.is_empty());
+        assert!(restored.state.observables.iter().chain(restored.state.unobservables.iter()).all(|obj| obj.key != event.key));
+        assert!(restored.state.observed.contains_key(&shower));
+        assert!(restored.state.is_gone(&shower));
+        assert!(unlock.is_met(&restored.state));
+        assert_eq!(restored.state.detail_levels_reached(), detail_levels);
+
+        // Even after another save and restore, long after it's over
+        restored.generation = event.end + 1;
+        restored.save_to(&mut storage).unwrap();
+        let restored = Game::load_from(&storage, Platform::headless()).unwrap().unwrap();
+        assert!(restored.state.observed.contains_key(&shower));
+        assert_eq!(restored.state.detail_levels_reached(), detail_levels);
+        assert!(restored.state.observables.iter().all(|obj| obj.key != event.key));
+    }
+
+    #[test]
+    fn objects_are_where_they_are_in_the_sky() {
+        let game = new_game(1);
+        let on_canvas = |key: &str| {
+            let p = game.position_of(&game_object(key)).unwrap();
+            assert!(p.x >= 0.0 && p.x <= game.view.width && p.y >= 0.0 && p.y <= game.view.height, "{} is at {:?}", key, p);
+            p
+        };
+        let sirius = on_canvas("sirius");
+        let m31 = on_canvas("m31");
+        on_canvas("aliens");
+
+        // Andromeda is well north of Sirius, and west of it (so to the right, looking up)
+        assert!(m31.y < sirius.y);
+        assert!(m31.x > sirius.x);
+    }
+
+    // Put an object somewhere on the canvas
+    fn move_to(game: &mut Game, key: &str, point: Point) {
+        let mut obj = game.state.observables.iter().find(|obj| obj.key == key).cloned().unwrap();
+        obj.coords = game.state.observer.ra_dec(game.view.unproject(point).unwrap());
+        game.state.update_object(obj);
+    }
+
+    #[test]
+    fn click_on_things() {
+        let sirius = game_object("sirius");
+        let at_sirius = new_game(1).position_of(&sirius).unwrap();
+
+        // Anywhere inside the hit radius (which the eye sets), but not outside it
+        let mut game = new_game(1);
+        let radius = game.hit_radius();
+        assert_eq!(radius, HIT_RADIUS_BASE + 4.0 * HIT_RADIUS_PER_POWER);
+        game.observe_at(at_sirius + Point::xy(radius - 0.5, 0.0));
+        assert!(game.state.observed.contains_key(&sirius));
+
+        let mut game = new_game(1);
+        game.observe_at(at_sirius + Point::xy(0.0, -(radius + 0.5)));
+        assert!(game.state.observed.is_empty());
+        assert!(game.slew.is_none());
+
+        // Between two things, the nearer one gets observed
+        let mut game = new_game(1);
+        move_to(&mut game, "m31", at_sirius + Point::xy(10.0, 0.0));
+        game.observe_at(at_sirius + Point::xy(4.0, 0.0));
+        assert_eq!(observed_keys(&game), vec![("sirius".to_string(), 0)]);
+
+        let mut game = new_game(1);
+        move_to(&mut game, "m31", at_sirius + Point::xy(10.0, 0.0));
+        game.observe_at(at_sirius + Point::xy(6.0, 0.0));
+        assert_eq!(observed_keys(&game), vec![("m31".to_string(), 0)]);
+
+        // Empty sky
+        let mut game = new_game(1);
+        let empty = Point::xy(game.view.width / 2.0, 20.0);
+        assert!(game.state.observables.iter()
+            .filter_map(|obj| game.position_of(obj))
+            .all(|p| p.distance(empty) > 2.0 * radius));
+        game.observe_at(empty);
+        assert!(game.state.observed.is_empty());
+        assert!(game.state.journal.is_empty());
+    }
+
+    #[test]
+    fn moonlight_washes_out_galaxies() {
+        let mut game = new_game(1);
+        let (m31, sirius) = (game_object("m31"), game_object("sirius"));
+        assert!(game.state.observables.contains(&m31));
+        assert_eq!(game.state.best_detail(&m31).map(|detail| detail.level), Some(0));
+
+        // Under a full moon, Andromeda's too faint for the naked eye, but Sirius isn't
+        game.state.weather = Weather { moonlight: 1.0, ..Weather::clear() };
+        game.state.refresh_observables();
+        assert!(game.state.best_detail(&m31).is_none());
+        assert!(!game.state.observables.contains(&m31));
+        assert!(game.state.observables.contains(&sirius));
+    }
+
+    #[test]
+    fn the_moon_and_planets_follow_their_orbits() {
+        let mut game = new_game(1);
+        let find = |game: &Game, key: &str| {
+            game.state.observables.iter().chain(game.state.unobservables.iter()).chain(game.state.observed.keys())
+                .find(|obj| obj.key == key).cloned().unwrap()
+        };
+        let start = game.state.observer.day;
+        for _ in 0..720 {
+            game.tick();
+        }
+        assert!((game.state.observer.day - start - 1.0).abs() < 1e-9);
+
+        // A day later the Moon has moved a long way against the stars, and Saturn hardly at all
+        let moved = |key: &str| find(&game, key).coords.separation(game_object(key).coords).to_degrees();
+        assert!((11.0..15.0).contains(&moved("moon")), "{}", moved("moon"));
+        assert!(moved("saturn") < 0.2);
+
+        // Right where the orbits say, and only the Moon and Venus have phases
+        let moon = find(&game, "moon");
+        assert!(moon.coords.separation(orbits::position(Body::Moon, game.state.observer.day)) < 1e-6);
+        assert!(moon.motion.unwrap().phase_at(game.generation).is_some());
+        assert!(find(&game, "venus").motion.unwrap().phase_at(game.generation).is_some());
+        assert!(find(&game, "jupiter").motion.unwrap().phase_at(game.generation).is_none());
+    }
+
+    #[test]
+    fn objects_rise_and_set() {
+        let mut game = new_game(1);
+        let sirius = game_object("sirius");
+        assert!(game.state.observables.contains(&sirius));
+
+        // Sirius sets during the evening...
+        while game.state.observer.is_up(sirius.coords) {
+            game.tick();
+        }
+        assert!(game.state.unobservables.contains(&sirius));
+        assert!(!game.state.observables.contains(&sirius));
+        assert_eq!(game.position_of(&sirius), None);
+        game.observe(sirius.clone());
+        assert!(game.state.observed.is_empty());
+
+        // ...and comes back up the next day
+        while !game.state.observer.is_up(sirius.coords) {
+            game.tick();
+        }
+        assert!(game.state.observables.contains(&sirius));
+        assert!(game.generation < 720);
+    }
+
+    #[test]
+    fn catch_up_after_time_away() {
+        let clock = Rc::new(ManualClock::new(1000.0));
+        let mut game = Game::new(Seed(7), Platform::new(Box::new(platform::SilentLogger), Box::new(clock.clone())));
+        game.init();
+        let mut storage = MemoryStorage::new();
+        game.save_to(&mut storage).unwrap();
+
+        // Ten minutes later...
+        clock.advance(10.0 * 60.0 * 1000.0);
+        let mut game = Game::load_from(&storage, Platform::new(Box::new(platform::SilentLogger), Box::new(clock.clone()))).unwrap().unwrap();
+        let summary = game.catch_up(away::MAX_AWAY_MS).unwrap();
+        assert_eq!(summary.ticks, 600);
+        assert_eq!(game.generation, 600);
+        assert_eq!(summary.photons, game.state.photons_earned);
+        assert_eq!(summary.observations.len(), 6); // Sirius, the Moon and the planets, as they come up
+        assert!(summary.photons > 0);
+
+        // Nothing more to catch up on
+        assert_eq!(game.catch_up(away::MAX_AWAY_MS), None);
+
+        // A long time away only counts up to the limit
+        clock.advance(100.0 * 60.0 * 1000.0);
+        let summary = game.catch_up(60.0 * 1000.0).unwrap();
+        assert_eq!(summary.ticks, 60);
+        assert!(summary.counted_ms < summary.away_ms);
+    }
+
+    #[test]
+    fn catch_up_on_a_whole_night() {
+        let clock = Rc::new(ManualClock::new(1000.0));
+        let mut game = Game::new(Seed(7), Platform::new(Box::new(platform::SilentLogger), Box::new(clock.clone())));
+        game.init();
+        game.tick();
+        clock.advance(away::MAX_AWAY_MS);
+
+        let summary = game.catch_up(away::MAX_AWAY_MS).unwrap();
+        assert_eq!(summary.ticks, 28_800);
+        assert_eq!(game.generation, 28_801);
+        assert!(summary.photons > 0);
+        assert!(!summary.observations.is_empty());
+    }
 }
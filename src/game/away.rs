@@ -0,0 +1,38 @@
+// What happened while the player was away, for when a saved game catches up
+
+use std::fmt;
+
+// Catch up on at most this much time away
+pub const MAX_AWAY_MS: f64 = 8.0 * 60.0 * 60.0 * 1000.0;
+
+// While the player's away, their telescope works through its observing queue,
+// making an observation this often
+pub const AWAY_TICKS_PER_OBSERVATION: i32 = 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AwaySummary {
+    pub away_ms: f64,
+    pub counted_ms: f64, // less than away_ms, if that was over the limit
+    pub ticks: i32,
+    pub photons: u64,
+    pub observations: Vec<String>, // names of the objects observed
+    pub telescopes: Vec<String>, // names of the telescopes acquired
+}
+
+impl fmt::Display for AwaySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = (self.away_ms / 60_000.0).floor();
+        write!(f, "While you were away for {} minutes", minutes)?;
+        if self.counted_ms < self.away_ms {
+            write!(f, " (only {} of them counted)", (self.counted_ms / 60_000.0).floor())?;
+        }
+        write!(f, ", your telescopes collected {} photons.", self.photons)?;
+        if !self.observations.is_empty() {
+            write!(f, "\nYou observed: {}.", self.observations.join(", "))?;
+        }
+        if !self.telescopes.is_empty() {
+            write!(f, "\nYou got: {}.", self.telescopes.join(", "))?;
+        }
+        Ok(())
+    }
+}
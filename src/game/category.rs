@@ -0,0 +1,365 @@
+// What makes each kind of object different. To add a kind, add a variant to
+// AstroObjectCategory, a Category for it here, and list it in ALL.
+
+use std::ops::RangeInclusive;
+use super::types::AstroObjectCategory;
+
+pub trait Category: Sync {
+    /// What it's called in game-data.json
+    fn key(&self) -> &'static str;
+
+    /// What it's called in a sentence
+    fn name(&self) -> &'static str;
+
+    fn sprite(&self) -> Sprite;
+
+    /// The shape of a detail ladder the deep field generates
+    fn ladder(&self) -> DetailLadder {
+        DetailLadder { levels: 2..=4, power_step: 1..=2 }
+    }
+
+    /// What each level of a generated detail ladder reveals, a few ways of saying it
+    /// each. A ladder stops when these run out, so categories the deep field doesn't
+    /// generate have none
+    fn detail_texts(&self) -> &'static [&'static [&'static str]] {
+        &[]
+    }
+
+    /// Whether it wanders across the sky, against the stars
+    fn moves(&self) -> bool {
+        false
+    }
+
+    /// Whether it's only around for a while
+    fn is_transient(&self) -> bool {
+        false
+    }
+
+    /// Whether it's faint and spread out, so that moonlight washes it out
+    fn is_diffuse(&self) -> bool {
+        false
+    }
+
+    /// How much more power it takes to see than usual, partway through its visit
+    /// (progress goes from 0 to 1). Transient things brighten and fade
+    fn dimming(&self, _progress: f64) -> i32 {
+        0
+    }
+}
+
+/// How an object's marked on the sky. Sizes are in px, at the wide-field scale
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sprite {
+    pub shape: Shape,
+    pub color: &'static str,
+    pub size: f64
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Shape {
+    Point, // a sharp dot
+    Disc, // a dot with a visible size
+    Fuzzy, // a soft glow with a bright core
+    Clump, // a few dots close together
+    Ring, // a faint shell
+    Tail, // a head with a tail streaming away
+    Saucer,
+    Burst, // streaks flying out from a point
+}
+
+/// How many levels of detail to generate, and how much more power each one takes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetailLadder {
+    pub levels: RangeInclusive<usize>,
+    pub power_step: RangeInclusive<i32>
+}
+
+const GALAXY_TEXTS: &[&[&str]] = &[
+    &["A faint smudge.",
+      "There's a tiny fuzzy patch here.",
+      "Something dim and fuzzy, too small to be a nebula."],
+    &["It's a spiral galaxy, seen face on.",
+      "It's an elliptical galaxy, a big ball of old stars.",
+      "It's a spiral galaxy, seen edge on, with a dark lane of dust across it."],
+    &["You can make out knots of bright young stars.",
+      "Its core is much brighter than the rest of it.",
+      "It's tugging on a smaller galaxy next to it."],
+    &["A supernova is going off in one of its arms!",
+      "A tail of stars trails away from it, pulled out in a collision.",
+      "There's a ring of new stars, rippling out from a collision."]
+];
+
+/// Every category, in the order they're listed in errors
+pub const ALL: &[AstroObjectCategory] = &[
+    AstroObjectCategory::Star,
+    AstroObjectCategory::Planet,
+    AstroObjectCategory::Moon,
+    AstroObjectCategory::Comet,
+    AstroObjectCategory::MeteorShower,
+    AstroObjectCategory::Supernova,
+    AstroObjectCategory::Exoplanet,
+    AstroObjectCategory::Nebula,
+    AstroObjectCategory::SupernovaRemnant,
+    AstroObjectCategory::OpenCluster,
+    AstroObjectCategory::GlobularCluster,
+    AstroObjectCategory::Galaxy,
+    AstroObjectCategory::GalaxyCluster,
+    AstroObjectCategory::Quasar,
+    AstroObjectCategory::AlienShip,
+];
+
+impl AstroObjectCategory {
+    pub fn behavior(&self) -> &'static dyn Category {
+        match self {
+            AstroObjectCategory::Star => &Star,
+            AstroObjectCategory::Planet => &Planet,
+            AstroObjectCategory::Moon => &Moon,
+            AstroObjectCategory::Comet => &Comet,
+            AstroObjectCategory::MeteorShower => &MeteorShower,
+            AstroObjectCategory::Supernova => &Supernova,
+            AstroObjectCategory::Exoplanet => &Exoplanet,
+            AstroObjectCategory::Nebula => &Nebula,
+            AstroObjectCategory::SupernovaRemnant => &SupernovaRemnant,
+            AstroObjectCategory::OpenCluster => &OpenCluster,
+            AstroObjectCategory::GlobularCluster => &GlobularCluster,
+            AstroObjectCategory::Galaxy => &Galaxy,
+            AstroObjectCategory::GalaxyCluster => &GalaxyCluster,
+            AstroObjectCategory::Quasar => &Quasar,
+            AstroObjectCategory::AlienShip => &AlienShip,
+        }
+    }
+}
+
+struct Star;
+
+impl Category for Star {
+    fn key(&self) -> &'static str { "star" }
+    fn name(&self) -> &'static str { "star" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Point, color: "#fffbe8", size: 2.5 }
+    }
+}
+
+struct Planet;
+
+impl Category for Planet {
+    fn key(&self) -> &'static str { "planet" }
+    fn name(&self) -> &'static str { "planet" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Disc, color: "#ffe2b0", size: 3.5 }
+    }
+    fn moves(&self) -> bool { true }
+}
+
+struct Moon;
+
+impl Category for Moon {
+    fn key(&self) -> &'static str { "moon" }
+    fn name(&self) -> &'static str { "moon" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Disc, color: "#f4f1e4", size: 6.0 }
+    }
+    fn moves(&self) -> bool { true }
+}
+
+struct Comet;
+
+impl Category for Comet {
+    fn key(&self) -> &'static str { "comet" }
+    fn name(&self) -> &'static str { "comet" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Tail, color: "#c8f0ff", size: 3.0 }
+    }
+    fn moves(&self) -> bool { true }
+    fn is_transient(&self) -> bool { true }
+    fn is_diffuse(&self) -> bool { true }
+    // Brightest halfway through, when it's closest to the sun
+    fn dimming(&self, progress: f64) -> i32 {
+        ((progress - 0.5).abs() * 8.0).round() as i32
+    }
+}
+
+struct MeteorShower;
+
+impl Category for MeteorShower {
+    fn key(&self) -> &'static str { "meteor_shower" }
+    fn name(&self) -> &'static str { "meteor shower" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Burst, color: "#fff4c0", size: 6.0 }
+    }
+    fn is_transient(&self) -> bool { true }
+}
+
+struct Supernova;
+
+impl Category for Supernova {
+    fn key(&self) -> &'static str { "supernova" }
+    fn name(&self) -> &'static str { "supernova" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Point, color: "#ffffff", size: 3.5 }
+    }
+    fn is_transient(&self) -> bool { true }
+    // Flares up over a few days, then fades away for weeks
+    fn dimming(&self, progress: f64) -> i32 {
+        const PEAK: f64 = 0.15;
+        let dimming = if progress < PEAK { (1.0 - progress / PEAK) * 4.0 } else { (progress - PEAK) / (1.0 - PEAK) * 6.0 };
+        dimming.round() as i32
+    }
+}
+
+struct Exoplanet;
+
+impl Category for Exoplanet {
+    fn key(&self) -> &'static str { "exoplanet" }
+    fn name(&self) -> &'static str { "exoplanet" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Point, color: "#ffd2a8", size: 2.0 }
+    }
+}
+
+struct Nebula;
+
+impl Category for Nebula {
+    fn key(&self) -> &'static str { "nebula" }
+    fn name(&self) -> &'static str { "nebula" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Fuzzy, color: "#ffb8d0", size: 5.0 }
+    }
+    fn is_diffuse(&self) -> bool { true }
+}
+
+struct SupernovaRemnant;
+
+impl Category for SupernovaRemnant {
+    fn key(&self) -> &'static str { "supernova_remnant" }
+    fn name(&self) -> &'static str { "supernova remnant" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Ring, color: "#b8ffd8", size: 4.5 }
+    }
+    fn is_diffuse(&self) -> bool { true }
+}
+
+struct OpenCluster;
+
+impl Category for OpenCluster {
+    fn key(&self) -> &'static str { "open_cluster" }
+    fn name(&self) -> &'static str { "open cluster" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Clump, color: "#d6f0ff", size: 4.0 }
+    }
+}
+
+struct GlobularCluster;
+
+impl Category for GlobularCluster {
+    fn key(&self) -> &'static str { "globular_cluster" }
+    fn name(&self) -> &'static str { "globular cluster" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Fuzzy, color: "#fff4ea", size: 3.5 }
+    }
+    fn is_diffuse(&self) -> bool { true }
+}
+
+struct Galaxy;
+
+impl Category for Galaxy {
+    fn key(&self) -> &'static str { "galaxy" }
+    fn name(&self) -> &'static str { "galaxy" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Fuzzy, color: "#fffbe8", size: 4.0 }
+    }
+    fn detail_texts(&self) -> &'static [&'static [&'static str]] {
+        GALAXY_TEXTS
+    }
+    fn is_diffuse(&self) -> bool { true }
+}
+
+struct GalaxyCluster;
+
+impl Category for GalaxyCluster {
+    fn key(&self) -> &'static str { "galaxy_cluster" }
+    fn name(&self) -> &'static str { "galaxy cluster" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Clump, color: "#fff4ea", size: 5.0 }
+    }
+    fn ladder(&self) -> DetailLadder {
+        DetailLadder { levels: 2..=4, power_step: 1..=3 }
+    }
+    fn detail_texts(&self) -> &'static [&'static [&'static str]] {
+        &[
+            &["A clump of faint smudges.",
+              "Several faint smudges sit suspiciously close together."],
+            &["Dozens of galaxies, all at the same distance. It's a cluster!",
+              "There are galaxies everywhere in here, crowded around a giant elliptical."],
+            &["Thin arcs curve around the middle: galaxies behind it, bent by its gravity.",
+              "The cluster's gravity is bending the light of galaxies far behind it into arcs."],
+            &["The arcs give away the cluster's mass, and most of it is dark matter.",
+              "One of the lensed arcs is a baby galaxy from the early universe."]
+        ]
+    }
+    fn is_diffuse(&self) -> bool { true }
+}
+
+struct Quasar;
+
+impl Category for Quasar {
+    fn key(&self) -> &'static str { "quasar" }
+    fn name(&self) -> &'static str { "quasar" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Point, color: "#c8d8ff", size: 2.0 }
+    }
+    // It's a dot until it suddenly isn't
+    fn ladder(&self) -> DetailLadder {
+        DetailLadder { levels: 2..=4, power_step: 2..=3 }
+    }
+    fn detail_texts(&self) -> &'static [&'static [&'static str]] {
+        &[
+            &["A faint point of light. It looks like a star, but it isn't one.",
+              "It's a dim blue dot, right where no star should be."],
+            &["Its light is stretched way into the red. It's billions of light years away!",
+              "Its spectrum is redshifted beyond anything in our galaxy."],
+            &["It flickers from night to night. Whatever's shining is smaller than a solar system.",
+              "It's brighter than a whole galaxy, from something the size of a solar system."],
+            &["A jet of plasma blasts out of it at nearly the speed of light.",
+              "A black hole a billion times the mass of the sun is feeding in there."]
+        ]
+    }
+}
+
+struct AlienShip;
+
+impl Category for AlienShip {
+    fn key(&self) -> &'static str { "alien_ship" }
+    fn name(&self) -> &'static str { "alien ship" }
+    fn sprite(&self) -> Sprite {
+        Sprite { shape: Shape::Saucer, color: "#b0ffb0", size: 4.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_category_is_listed_once() {
+        let mut keys: Vec<&str> = ALL.iter().map(|category| category.behavior().key()).collect();
+        for category in ALL.iter() {
+            assert_eq!(&category.behavior().key().parse::<AstroObjectCategory>().unwrap(), category);
+            let ladder = category.behavior().ladder();
+            assert!(*ladder.levels.start() >= 1 && *ladder.power_step.start() >= 1);
+            assert!(category.behavior().detail_texts().iter().all(|ways| !ways.is_empty()));
+        }
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), ALL.len());
+    }
+
+    // The deep field's ladders never get cut short
+    #[test]
+    fn deep_field_categories_have_texts_for_every_level() {
+        for category in [AstroObjectCategory::Galaxy, AstroObjectCategory::GalaxyCluster, AstroObjectCategory::Quasar] {
+            let behavior = category.behavior();
+            assert!(behavior.detail_texts().len() >= *behavior.ladder().levels.end(), "{}", behavior.name());
+        }
+    }
+}
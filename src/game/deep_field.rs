@@ -0,0 +1,220 @@
+// Procedurally generated galaxies, galaxy clusters and quasars, made a region and
+// a layer of depth at a time, each from its own stream, so they always come out the same.
+
+use std::fmt;
+use std::str::FromStr;
+use std::f64::consts;
+use anyhow::*;
+use rand::prelude::*;
+use rand::rngs::SmallRng;
+use crate::points::{SkyCoord, mod360};
+use super::seed::{self, Seed};
+use super::types::{AstroObject, AstroObjectCategory, AstroDetail};
+
+// Every generated object's key starts with this, so the game data can't use it
+pub const KEY_PREFIX: &str = "df_";
+
+// Regions are this many degrees on a side, in ra and dec
+const REGION_DEGREES: f64 = 15.0;
+
+// The shallowest layer needs this much power to see anything, and each layer
+// after that needs a bit more
+const BASE_POWER: i32 = 10;
+const POWER_PER_DEPTH: i32 = 2;
+
+const OBJECTS_PER_LAYER: std::ops::RangeInclusive<usize> = 2..=4;
+
+// Quasars are rare, and too far away to see in the shallow layers
+const QUASAR_MIN_DEPTH: u32 = 2;
+
+/// A patch of sky, by column (ra) and row (dec, from the south pole)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Region {
+    pub ra: u32,
+    pub dec: u32
+}
+
+impl Region {
+    pub fn containing(coords: SkyCoord) -> Self {
+        let size = REGION_DEGREES.to_radians();
+        let columns = (360.0 / REGION_DEGREES) as u32;
+        let rows = (180.0 / REGION_DEGREES) as u32;
+        let ra = (mod360(coords.ra) / size).floor() as u32;
+        let dec = ((coords.dec + consts::FRAC_PI_2) / size).floor() as u32;
+        Self { ra: ra.min(columns - 1), dec: dec.min(rows - 1) }
+    }
+
+    /// Every region in the sky
+    pub fn all() -> impl Iterator<Item = Region> {
+        let columns = (360.0 / REGION_DEGREES) as u32;
+        let rows = (180.0 / REGION_DEGREES) as u32;
+        (0..rows).flat_map(move |dec| (0..columns).map(move |ra| Region { ra, dec }))
+    }
+
+    pub fn center(&self) -> SkyCoord {
+        SkyCoord::degrees(
+            (self.ra as f64 + 0.5) * REGION_DEGREES,
+            (self.dec as f64 + 0.5) * REGION_DEGREES - 90.0
+        )
+    }
+
+    /// The key this region's saved under, and that its random stream comes from
+    pub fn key(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "r{}d{}", self.ra, self.dec)
+    }
+}
+
+impl FromStr for Region {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = || -> Option<Region> {
+            let (ra, dec) = s.strip_prefix('r')?.split_once('d')?;
+            Some(Region { ra: ra.parse().ok()?, dec: dec.parse().ok()? })
+        };
+        parse().with_context(|| format!("\"{}\" isn't a sky region", s))
+    }
+}
+
+/// The power needed to see anything in a layer
+pub fn power_at_depth(depth: u32) -> i32 {
+    BASE_POWER + POWER_PER_DEPTH * depth as i32
+}
+
+/// How many layers of a region are worth generating with some resolving power:
+/// every one it can see into, and the next one down, to aim for
+pub fn depths_for_power(power: i32) -> u32 {
+    let reachable = (power - BASE_POWER).div_euclid(POWER_PER_DEPTH) + 1;
+    std::cmp::max(reachable, 0) as u32 + 1
+}
+
+/// The objects in one layer of a region. Always the same for the same seed, region and depth
+pub fn generate(seed: Seed, region: Region, depth: u32) -> Vec<AstroObject> {
+    let mut rng = SmallRng::seed_from_u64(seed.derive(&format!("{}/{}/{}", seed::DEEP_FIELD, region.key(), depth)));
+    let count = rng.gen_range(OBJECTS_PER_LAYER);
+    (0..count).map(|i| generate_object(&mut rng, region, depth, i)).collect()
+}
+
+fn generate_object(rng: &mut SmallRng, region: Region, depth: u32, i: usize) -> AstroObject {
+    let category = match rng.gen_range(0..10) {
+        0 if depth >= QUASAR_MIN_DEPTH => AstroObjectCategory::Quasar,
+        0..=2 => AstroObjectCategory::GalaxyCluster,
+        _ => AstroObjectCategory::Galaxy
+    };
+
+    // Anywhere in the region, evenly spread over the sphere
+    let size = REGION_DEGREES.to_radians();
+    let ra = rng.gen_range(0.0..size) + region.ra as f64 * size;
+    let dec0 = region.dec as f64 * size - consts::FRAC_PI_2;
+    let dec = rng.gen_range(dec0.sin()..(dec0 + size).sin()).asin();
+    let coords = SkyCoord::radians(ra, dec);
+
+    let power_needed = power_at_depth(depth) + rng.gen_range(0..POWER_PER_DEPTH);
+    let ladder = category.behavior().ladder();
+    let texts = category.behavior().detail_texts();
+    let levels = std::cmp::min(rng.gen_range(ladder.levels.clone()), texts.len());
+    let mut level_power = power_needed;
+    let detail: Vec<AstroDetail> = (0..levels).map(|level| {
+        if level > 0 {
+            level_power += rng.gen_range(ladder.power_step.clone());
+        }
+        AstroDetail {
+            level,
+            power_needed: level_power,
+            requires: vec![],
+            discovery_text: texts[level].choose(rng).unwrap().to_string()
+        }
+    }).collect();
+
+    // Quasars are much further away than they look
+    let distance_ly = match category {
+        AstroObjectCategory::Quasar => 10f64.powf(rng.gen_range(9.3..10.1)),
+        _ => 10f64.powf(7.0 + 0.3 * depth as f64 + rng.gen_range(0.0..0.3))
+    };
+
+    AstroObject {
+        key: format!("{}{}_{}_{}", KEY_PREFIX, region.key(), depth, i),
+        name: format!("{} {}", designation_prefix(&category), j_designation(coords)),
+        category,
+        power_needed,
+        coords,
+        magnitude: Some(power_needed as f64 - 1.5 + rng.gen_range(0.0..1.0)),
+        distance_ly: Some(distance_ly.round()),
+        motion: None,
+        detail
+    }
+}
+
+fn designation_prefix(category: &AstroObjectCategory) -> &'static str {
+    match category {
+        AstroObjectCategory::Quasar => "DFQ",
+        AstroObjectCategory::GalaxyCluster => "DFC",
+        _ => "DFG"
+    }
+}
+
+// Catalogs name faint things after where they are: "J014212+3518" is at
+// ra 01h42m12s, dec +35deg18'
+fn j_designation(coords: SkyCoord) -> String {
+    let seconds = (mod360(coords.ra).to_degrees() / 15.0 * 3600.0).floor() as u32;
+    let arcminutes = (coords.dec.to_degrees().abs() * 60.0).floor() as u32;
+    format!(
+        "J{:02}{:02}{:02}{}{:02}{:02}",
+        seconds / 3600 % 24, seconds / 60 % 60, seconds % 60,
+        if coords.dec < 0.0 { '-' } else { '+' },
+        arcminutes / 60, arcminutes % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regions_regenerate_identically() {
+        let region = Region::containing(SkyCoord::degrees(10.685, 41.269));
+        assert_eq!(region.key().parse::<Region>().unwrap(), region);
+        assert!("x1d2".parse::<Region>().is_err());
+
+        for depth in 0..5 {
+            let objects = generate(Seed(1), region, depth);
+            assert_eq!(objects.iter().map(|obj| &obj.name).collect::<Vec<_>>(),
+                       generate(Seed(1), region, depth).iter().map(|obj| &obj.name).collect::<Vec<_>>());
+            for obj in objects.iter() {
+                assert_eq!(Region::containing(obj.coords), region, "{} wandered off", obj.name);
+            }
+        }
+        let other = Region { ra: region.ra + 1, ..region };
+        assert_ne!(generate(Seed(1), region, 0)[0].name, generate(Seed(1), other, 0)[0].name);
+        assert_ne!(generate(Seed(1), region, 0)[0].name, generate(Seed(2), region, 0)[0].name);
+    }
+
+    #[test]
+    fn deeper_is_harder() {
+        let mut keys = std::collections::HashSet::new();
+        for region in Region::all().step_by(7) {
+            for depth in 0..10 {
+                for obj in generate(Seed(5), region, depth) {
+                    assert!(keys.insert(obj.key.clone()), "{} is generated twice", obj.key);
+                    assert!(obj.power_needed >= power_at_depth(depth) && obj.power_needed < power_at_depth(depth + 1));
+                    assert_eq!(obj.power_needed, obj.detail[0].power_needed);
+                    assert!(obj.detail.windows(2).all(|pair| pair[0].power_needed < pair[1].power_needed));
+                    if depth < QUASAR_MIN_DEPTH {
+                        assert_ne!(obj.category, AstroObjectCategory::Quasar);
+                    }
+                }
+            }
+        }
+
+        assert_eq!(depths_for_power(4), 1);
+        assert_eq!(depths_for_power(10), 2);
+        assert_eq!(depths_for_power(13), 3);
+        assert_eq!(j_designation(SkyCoord::degrees(25.55, -35.31)), "J014212-3518");
+    }
+}
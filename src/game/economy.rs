@@ -0,0 +1,40 @@
+// Photons: what telescopes collect, what detail levels bring in, and what things cost.
+// All of the numbers live here so they can be tuned in one place.
+
+use std::cmp;
+use super::types::GameState;
+
+// Photons a telescope collects per tick, for each point of resolving power
+pub const PHOTONS_PER_POWER: u64 = 1;
+
+// Photons per tick for each detail level reached, summed over all observed objects
+pub const PHOTONS_PER_DETAIL_LEVEL: u64 = 2;
+
+pub fn telescope_income(max_power: i32) -> u64 {
+    (cmp::max(max_power, 0) as u64).saturating_mul(PHOTONS_PER_POWER)
+}
+
+pub fn detail_income(detail_levels: usize) -> u64 {
+    (detail_levels as u64).saturating_mul(PHOTONS_PER_DETAIL_LEVEL)
+}
+
+/// How many photons the player collects per tick
+pub fn income(state: &GameState) -> u64 {
+    state.telescopes.iter()
+        .map(|scope| telescope_income(scope.max_power))
+        .fold(detail_income(state.detail_levels_reached()), u64::saturating_add)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn income_formulas() {
+        assert_eq!(telescope_income(4), 4);
+        assert_eq!(telescope_income(-3), 0);
+        assert_eq!(detail_income(0), 0);
+        assert_eq!(detail_income(5), 10);
+        assert_eq!(detail_income(usize::MAX), u64::MAX);
+    }
+}
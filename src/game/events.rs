@@ -0,0 +1,154 @@
+// Things that only come around for a while: supernovae, comets, meteor showers.
+// Each kind's schedule is split into slots, and the seed decides each slot's visit.
+
+use rand::prelude::*;
+use rand::rngs::SmallRng;
+use crate::points::SkyCoord;
+use super::seed::{self, Seed};
+use super::types::{AstroObject, AstroObjectCategory, AstroDetail, Motion};
+
+/// A kind of event, and how often it comes around
+#[derive(Debug, Clone)]
+pub struct EventKind {
+    pub key: String,
+    pub name: String,
+    pub category: AstroObjectCategory,
+    pub every: i32, // generations per slot
+    pub chance: f64, // that a slot gets a visit
+    pub duration: i32, // in generations; less than every
+    pub bonus: u64, // photons for observing it before it's gone
+    pub coords: Option<SkyCoord>, // where it shows up. Anywhere, if it's not given
+    pub speed: f64, // how fast it moves across the sky (radians per tick)
+    pub detail: Vec<AstroDetail>
+}
+
+/// One visit of an event
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub key: String,
+    pub start: i32,
+    pub end: i32, // the first generation it's gone
+    pub bonus: u64,
+    object: AstroObject // as it is at its brightest
+}
+
+impl Event {
+    /// How it looks at a generation. It's harder to see when it's dimmer
+    pub fn object_at(&self, generation: i32) -> AstroObject {
+        let progress = (generation - self.start) as f64 / (self.end - self.start) as f64;
+        let dimming = self.object.category.behavior().dimming(progress.clamp(0.0, 1.0));
+        let mut obj = self.object.clone();
+        for detail in obj.detail.iter_mut() {
+            detail.power_needed += dimming;
+        }
+        obj.power_needed = obj.detail[0].power_needed;
+        if let Some(motion) = obj.motion {
+            obj.coords = motion.position_at(generation);
+        }
+        obj
+    }
+
+    pub fn is_over(&self, generation: i32) -> bool {
+        generation >= self.end
+    }
+}
+
+/// Every event going on at a generation
+pub fn events_at(seed: Seed, kinds: &[EventKind], generation: i32) -> Vec<Event> {
+    kinds.iter()
+        .filter_map(|kind| visit(seed, kind, generation.div_euclid(kind.every)))
+        .filter(|event| event.start <= generation && generation < event.end)
+        .collect()
+}
+
+/// The visit an object's key came from, even if it's long over. Keys look like "supernova_12"
+pub fn event_for_key(seed: Seed, kinds: &[EventKind], key: &str) -> Option<Event> {
+    let (kind_key, slot) = key.rsplit_once('_')?;
+    let kind = kinds.iter().find(|kind| kind.key == kind_key)?;
+    visit(seed, kind, slot.parse().ok()?)
+}
+
+// The visit in a slot, if there is one
+fn visit(seed: Seed, kind: &EventKind, slot: i32) -> Option<Event> {
+    let mut rng = SmallRng::seed_from_u64(seed.derive(&format!("{}/{}/{}", seed::EVENTS, kind.key, slot)));
+    if rng.gen::<f64>() >= kind.chance {
+        return None;
+    }
+    let start = slot * kind.every + rng.gen_range(0..=kind.every - kind.duration);
+    let coords = kind.coords.unwrap_or_else(|| {
+        // Anywhere, evenly spread over the sphere
+        SkyCoord::radians(rng.gen_range(0.0..std::f64::consts::TAU), rng.gen_range(-1.0f64..1.0).asin())
+    });
+    let motion = if kind.speed > 0.0 {
+        let heading: f64 = rng.gen_range(0.0..std::f64::consts::TAU);
+        Some(Motion::Drift {
+            from: coords,
+            since: start,
+            ra_per_tick: kind.speed * heading.cos() / coords.dec.cos().max(0.1),
+            dec_per_tick: kind.speed * heading.sin()
+        })
+    }
+    else {
+        None
+    };
+
+    let key = format!("{}_{}", kind.key, slot);
+    Some(Event {
+        object: AstroObject {
+            key: key.clone(),
+            name: format!("{} {}", kind.name, slot + 1),
+            category: kind.category.clone(),
+            power_needed: kind.detail[0].power_needed,
+            coords,
+            magnitude: None,
+            distance_ly: None,
+            motion,
+            detail: kind.detail.clone()
+        },
+        key,
+        start,
+        end: start + kind.duration,
+        bonus: kind.bonus
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::data;
+
+    #[test]
+    fn events_come_and_go() {
+        let kinds = data::game_data().events;
+        let supernova = kinds.iter().find(|kind| kind.category == AstroObjectCategory::Supernova).unwrap();
+
+        let mut seen = std::collections::HashMap::new();
+        for generation in 0..20000 {
+            for event in events_at(Seed(1), &kinds, generation) {
+                assert!(!event.is_over(generation));
+                let (first, last) = seen.entry(event.key.clone()).or_insert((generation, generation));
+                *last = generation;
+                assert_eq!(*first, event.start);
+            }
+        }
+        // Every visit lasts as long as it should, and the same seed gets the same ones
+        assert!(seen.len() > 10);
+        for (key, (first, last)) in seen.iter() {
+            let kind = kinds.iter().find(|kind| key.starts_with(&kind.key)).unwrap();
+            if first + kind.duration <= 20000 {
+                assert_eq!(last - first + 1, kind.duration, "{}", key);
+            }
+        }
+        let keys = |seed| events_at(seed, &kinds, 12345).into_iter().map(|event| event.key).collect::<Vec<_>>();
+        assert_eq!(keys(Seed(1)), keys(Seed(1)));
+
+        // A supernova flares up, then fades
+        let event = (0..).find_map(|slot| visit(Seed(1), supernova, slot)).unwrap();
+        let power = |t: f64| {
+            let generation = event.start + ((event.end - event.start - 1) as f64 * t) as i32;
+            event.object_at(generation).power_needed
+        };
+        assert!(power(0.0) > power(0.15));
+        assert!(power(0.15) < power(0.9));
+    }
+}
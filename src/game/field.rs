@@ -0,0 +1,151 @@
+// The faint stars a zoomed-in telescope shows, made on demand a patch of sky at a time.
+// Fainter steps get smaller patches, so a wide view never makes many faint ones.
+
+use std::f64::consts;
+use rand::prelude::*;
+use rand::rngs::SmallRng;
+use crate::points::SkyCoord;
+use super::seed::{self, Seed};
+
+// The brightest field stars. Anything brighter is part of the wide-field sky
+const BRIGHTEST_MAGNITUDE: f64 = 3.0;
+
+// Each band is a magnitude wide. The brightest band's patches are this big, and
+// every band's patches cover half the area of the one before
+const BAND_CELL_DEGREES: f64 = 8.0;
+
+// On average, in a patch near the celestial equator
+const STARS_PER_CELL: f64 = 2.0;
+
+// Don't make a band of stars that would need more patches than this; they'd be
+// too small to see
+const MAX_CELLS: usize = 2000;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FieldStar {
+    pub coords: SkyCoord,
+    pub magnitude: f64
+}
+
+/// The field stars within a radius (radians) of somewhere, down to a magnitude
+pub fn field_stars(seed: Seed, center: SkyCoord, radius: f64, faintest: f64) -> Vec<FieldStar> {
+    let stream = seed.derive(seed::FIELD);
+    let bands = (faintest - BRIGHTEST_MAGNITUDE).ceil().max(0.0) as u32;
+
+    (0..bands).flat_map(|band| {
+        let cells = cells_near(band, center, radius);
+        if cells.len() > MAX_CELLS {
+            return vec![];
+        }
+        cells.into_iter().flat_map(|cell| cell_stars(stream, cell)).collect()
+    })
+    .filter(|star| star.magnitude <= faintest && center.separation(star.coords) <= radius)
+    .collect()
+}
+
+// A patch of sky: the band, and which column (ra) and row (dec) it's in
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Cell {
+    band: u32,
+    ra: i64,
+    dec: i64
+}
+
+fn cell_size(band: u32) -> f64 {
+    BAND_CELL_DEGREES.to_radians() / 2f64.powf(band as f64 / 2.0)
+}
+
+fn columns(band: u32) -> i64 {
+    (consts::TAU / cell_size(band)).ceil() as i64
+}
+
+// Every cell of a band that could have stars within the radius
+fn cells_near(band: u32, center: SkyCoord, radius: f64) -> Vec<Cell> {
+    let size = cell_size(band);
+    let row = |dec: f64| ((dec.clamp(-consts::FRAC_PI_2, consts::FRAC_PI_2) + consts::FRAC_PI_2) / size).floor() as i64;
+    let rows = row(center.dec - radius)..=row(center.dec + radius);
+
+    // Near a pole, the circle takes in every ra
+    let columns = columns(band);
+    let ra_range = if center.dec.abs() + radius >= consts::FRAC_PI_2 {
+        0..columns
+    }
+    else {
+        let half_width = (radius.sin() / center.dec.cos()).clamp(-1.0, 1.0).asin();
+        let first = ((center.ra - half_width) / size).floor() as i64;
+        let last = ((center.ra + half_width) / size).floor() as i64;
+        first..std::cmp::min(last + 1, first + columns)
+    };
+
+    rows.flat_map(|dec| ra_range.clone().map(move |ra| Cell { band, ra: ra.rem_euclid(columns), dec }))
+        .collect()
+}
+
+// The stars in a cell. The last column is narrower, and cells near the poles
+// are smaller, so they get fewer stars
+fn cell_stars(stream: u64, cell: Cell) -> Vec<FieldStar> {
+    let size = cell_size(cell.band);
+    let ra0 = cell.ra as f64 * size;
+    let ra1 = (ra0 + size).min(consts::TAU);
+    let dec0 = (cell.dec as f64 * size - consts::FRAC_PI_2).min(consts::FRAC_PI_2);
+    let dec1 = (dec0 + size).min(consts::FRAC_PI_2);
+    if ra1 <= ra0 || dec1 <= dec0 {
+        return vec![];
+    }
+
+    let mut rng = cell_rng(stream, cell);
+    let area = (ra1 - ra0) * (dec1.sin() - dec0.sin());
+    let expected = STARS_PER_CELL * area / (size * size);
+    let count = expected.floor() as usize + if rng.gen::<f64>() < expected.fract() { 1 } else { 0 };
+
+    let magnitude = BRIGHTEST_MAGNITUDE + cell.band as f64;
+    (0..count).map(|_| FieldStar {
+        // Even over the sphere, so sin(dec) is uniform
+        coords: SkyCoord::radians(rng.gen_range(ra0..ra1), rng.gen_range(dec0.sin()..dec1.sin()).asin()),
+        magnitude: magnitude + rng.gen::<f64>()
+    }).collect()
+}
+
+fn cell_rng(stream: u64, cell: Cell) -> SmallRng {
+    let mix = (cell.band as u64).wrapping_mul(0x9e3779b97f4a7c15)
+        ^ (cell.ra as u64).wrapping_mul(0xbf58476d1ce4e5b9)
+        ^ (cell.dec as u64).wrapping_mul(0x94d049bb133111eb);
+    SmallRng::seed_from_u64(stream ^ mix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_spot_looks_the_same() {
+        let m31 = SkyCoord::degrees(10.685, 41.269);
+        let radius = 1f64.to_radians();
+        let stars = field_stars(Seed(1), m31, radius, 16.0);
+        assert!(!stars.is_empty());
+        assert_eq!(stars, field_stars(Seed(1), m31, radius, 16.0));
+        assert_ne!(stars, field_stars(Seed(2), m31, radius, 16.0));
+
+        // A smaller view shows the same stars, just fewer of them
+        let closer = field_stars(Seed(1), m31, radius / 2.0, 16.0);
+        assert!(closer.len() < stars.len());
+        assert!(closer.iter().all(|star| stars.contains(star)));
+    }
+
+    #[test]
+    fn more_power_shows_fainter_stars() {
+        let spot = SkyCoord::degrees(200.0, -30.0);
+        let radius = 0.5f64.to_radians();
+        let bright = field_stars(Seed(3), spot, radius, 10.0);
+        let faint = field_stars(Seed(3), spot, radius, 18.0);
+        assert!(faint.len() > bright.len());
+        assert!(bright.iter().all(|star| star.magnitude <= 10.0 && faint.contains(star)));
+
+        // Near the pole, and straddling ra 0
+        for spot in [SkyCoord::degrees(0.0, 89.8), SkyCoord::degrees(359.9, 10.0)].iter() {
+            let stars = field_stars(Seed(3), *spot, radius, 16.0);
+            assert!(!stars.is_empty());
+            assert!(stars.iter().all(|star| spot.separation(star.coords) <= radius));
+        }
+    }
+}
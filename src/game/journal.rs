@@ -0,0 +1,145 @@
+// The observing journal. Entries are saved as they are, so something that's gone
+// (like a faded supernova) stays in it; only the latest MAX_ENTRIES are kept.
+
+use serde::{Serialize, Deserialize};
+use crate::orbits;
+use super::types::AstroObjectCategory;
+use super::weather::Weather;
+
+// Enough for a good long game, and small enough to save every tick
+pub const MAX_ENTRIES: usize = 500;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub generation: i32,
+    pub day: f64, // the date in the sky, as a day number (see orbits)
+
+    pub object: String, // the object's key
+    pub name: String,
+    pub category: String, // the category's key
+    pub level: usize, // the detail level it reached
+    pub discoveries: Vec<String>, // everything that was new this time
+
+    pub telescope: String, // the telescope's key
+    pub site: Option<String>, // and the site's
+    pub power: i32, // how much resolving power the player had on the night
+    pub weather: Weather
+}
+
+impl Entry {
+    /// One line of text, for exporting
+    pub fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\tlevel {}\t{}\t{}\tpower {}\t{}\t{}",
+            orbits::date(self.day), self.name, self.category, self.level + 1,
+            self.telescope, self.site.as_deref().unwrap_or("-"), self.power,
+            self.weather, self.discoveries.join(" ")
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Journal {
+    entries: Vec<Entry>
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write up an observation. Once the journal's full, the oldest entry goes
+    pub fn record(&mut self, entry: Entry) {
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.drain(..=self.entries.len() - MAX_ENTRIES);
+        }
+        self.entries.push(entry);
+    }
+
+    /// Everything, oldest first
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn for_object<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Entry> {
+        self.entries.iter().filter(move |entry| entry.object == key)
+    }
+
+    pub fn for_category(&self, category: &AstroObjectCategory) -> impl Iterator<Item = &Entry> {
+        let key = category.behavior().key();
+        self.entries.iter().filter(move |entry| entry.category == key)
+    }
+
+    pub fn with_telescope<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Entry> {
+        self.entries.iter().filter(move |entry| entry.telescope == key)
+    }
+
+    /// The whole journal as tab-separated text, with a header, for players to keep
+    pub fn export(&self) -> String {
+        let header = "date (UT)\tobject\tcategory\tdetail\ttelescope\tsite\tpower\tconditions\tnotes";
+        std::iter::once(header.to_string())
+            .chain(self.entries.iter().map(|entry| entry.to_line()))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(object: &str, category: &str, telescope: &str, level: usize) -> Entry {
+        Entry {
+            generation: 10,
+            day: 9376.5,
+            object: object.to_string(),
+            name: object.to_uppercase(),
+            category: category.to_string(),
+            level,
+            discoveries: vec![format!("Level {} of {}.", level, object)],
+            telescope: telescope.to_string(),
+            site: None,
+            power: 4,
+            weather: Weather::clear()
+        }
+    }
+
+    #[test]
+    fn look_things_up() {
+        let mut journal = Journal::new();
+        journal.record(entry("m31", "galaxy", "eye", 0));
+        journal.record(entry("sirius", "star", "eye", 0));
+        journal.record(entry("m31", "galaxy", "reflector_6in", 2));
+        assert_eq!(journal.len(), 3);
+
+        let levels: Vec<usize> = journal.for_object("m31").map(|entry| entry.level).collect();
+        assert_eq!(levels, vec![0, 2]);
+        assert_eq!(journal.for_category(&AstroObjectCategory::Star).count(), 1);
+        assert_eq!(journal.for_category(&AstroObjectCategory::Quasar).count(), 0);
+        assert_eq!(journal.with_telescope("eye").count(), 2);
+
+        let export = journal.export();
+        assert_eq!(export.lines().count(), 4);
+        assert!(export.lines().nth(3).unwrap().starts_with("2025-09-01 12:00\tM31\tgalaxy\tlevel 3\treflector_6in"));
+    }
+
+    #[test]
+    fn keep_the_latest_entries() {
+        let mut journal = Journal::new();
+        for level in 0..MAX_ENTRIES + 10 {
+            journal.record(entry("m31", "galaxy", "eye", level));
+        }
+        assert_eq!(journal.len(), MAX_ENTRIES);
+        assert_eq!(journal.entries()[0].level, 10);
+        assert_eq!(journal.entries()[MAX_ENTRIES - 1].level, MAX_ENTRIES + 9);
+    }
+}
@@ -0,0 +1,756 @@
+// Loads the game's content from JSON. serde reads each entry into a loose "raw"
+// struct, then we validate it, so an error can point at the exact entry that's broken.
+
+use anyhow::*;
+use serde::Deserialize;
+use serde_json::Value;
+use std::cmp;
+use std::collections::HashSet;
+use crate::points::{Projection, SkyCoord, SkyView};
+use crate::orbits::{self, Body};
+use super::types::*;
+use super::sky::{self, Observer};
+use super::deep_field;
+use super::events::EventKind;
+
+pub struct GameData {
+    pub objects: Vec<AstroObject>,
+    pub telescopes: TelescopeIndex,
+    pub sites: SiteIndex,
+    pub accessories: AccessoryIndex,
+    pub events: Vec<EventKind>,
+    pub view: SkyView,
+    pub observer: Observer
+}
+
+#[derive(Deserialize)]
+struct RawGameData {
+    display: RawDisplay,
+    view: RawView,
+    observer: RawObserver,
+    #[serde(default)]
+    telescopes: Vec<Value>,
+    #[serde(default)]
+    sites: Vec<Value>,
+    #[serde(default)]
+    accessories: Vec<Value>,
+    #[serde(default)]
+    objects: Vec<Value>,
+    #[serde(default)]
+    events: Vec<Value>
+}
+
+#[derive(Deserialize)]
+struct RawDisplay {
+    width: f64,
+    height: f64
+}
+
+// Which way the player looks, in their own sky. Angles are in degrees
+#[derive(Deserialize)]
+struct RawView {
+    az: f64,
+    alt: f64,
+    fov: f64,
+    projection: Option<String> // stereographic (the default), gnomonic or orthographic
+}
+
+// Where the player is, and the local sidereal time (in degrees) when the game starts
+#[derive(Deserialize)]
+struct RawObserver {
+    latitude: f64,
+    lst: f64,
+    date: Option<String> // when the game starts, as "YYYY-MM-DD"; 2000-01-01 if there's no date
+}
+
+#[derive(Deserialize)]
+struct RawTelescope {
+    key: String,
+    name: String,
+    #[serde(default)]
+    description: String,
+    max_power: i32,
+    #[serde(default)]
+    unlock: Vec<Unlock>,
+    #[serde(default)]
+    price: u64,
+    weather_sensitivity: Option<f64>, // 1 if it's not given
+    fov: f64, // degrees
+    slew_speed: f64, // degrees per tick
+    image: Option<String> // assets/telescopes/<key>.png if it's not given
+}
+
+#[derive(Deserialize)]
+struct RawSite {
+    key: String,
+    name: String,
+    #[serde(default)]
+    description: String,
+    bortle: u8,
+    sky_color: String,
+    #[serde(default)]
+    unlock: Vec<Unlock>,
+    #[serde(default)]
+    price: u64
+}
+
+#[derive(Deserialize)]
+struct RawAccessory {
+    key: String,
+    name: String,
+    #[serde(default)]
+    description: String,
+    kind: String,
+    #[serde(default)]
+    power_bonus: i32,
+    fov: Option<f64>, // degrees, and only for eyepieces
+    #[serde(default)]
+    provides: Vec<String>,
+    fits: Vec<String>,
+    #[serde(default)]
+    unlock: Vec<Unlock>,
+    #[serde(default)]
+    price: u64
+}
+
+#[derive(Deserialize)]
+struct RawObject {
+    key: Option<String>,
+    name: String,
+    category: String,
+    ra: Option<f64>, // degrees, not hours; everything needs an ra and dec except the orbiting things
+    dec: Option<f64>,
+    magnitude: Option<f64>,
+    distance_ly: Option<f64>,
+    motion: Option<RawMotion>, // only for things that move, like comets
+    orbit: Option<String>, // a body from orbits, for the Moon and planets
+    detail: Vec<RawDetail>
+}
+
+// How far something drifts in a day of sky time, in degrees
+#[derive(Deserialize)]
+struct RawMotion {
+    ra: f64,
+    dec: f64
+}
+
+// A kind of transient event, and its schedule. Where it shows up is random
+// unless it has an ra and dec (like a meteor shower's radiant)
+#[derive(Deserialize)]
+struct RawEvent {
+    key: String,
+    name: String,
+    category: String,
+    every: i32, // generations
+    #[serde(default = "always")]
+    chance: f64,
+    duration: i32, // generations
+    #[serde(default)]
+    bonus: u64, // photons
+    ra: Option<f64>,
+    dec: Option<f64>,
+    speed: Option<f64>, // degrees per day of sky time
+    detail: Vec<RawDetail>
+}
+
+fn always() -> f64 {
+    1.0
+}
+
+// Each level of detail needs either a resolving power or an apparent magnitude
+#[derive(Deserialize)]
+struct RawDetail {
+    level: usize,
+    power_needed: Option<i32>,
+    magnitude: Option<f64>,
+    #[serde(default)]
+    requires: Vec<String>, // things an accessory has to provide
+    description: String
+}
+
+impl GameData {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let raw: RawGameData = serde_json::from_str(json).context("game data isn't valid JSON")?;
+
+        let view = parse_view(&raw.view, &raw.display).context("view")?;
+        let observer = parse_observer(&raw.observer).context("observer")?;
+
+        let mut telescopes = TelescopeIndex::new();
+        for (tier, entry) in raw.telescopes.into_iter().enumerate() {
+            let scope = parse_telescope(entry, tier).with_context(|| format!("telescopes[{}]", tier))?;
+            if telescopes.contains_key(&scope.key) {
+                bail!("telescopes[{}]: there's already a telescope with key \"{}\"", tier, scope.key);
+            }
+            telescopes.insert(scope.key.clone(), scope);
+        }
+
+        let mut sites = SiteIndex::new();
+        for (tier, entry) in raw.sites.into_iter().enumerate() {
+            let site = parse_site(entry, tier).with_context(|| format!("sites[{}]", tier))?;
+            if sites.contains_key(&site.key) {
+                bail!("sites[{}]: there's already a site with key \"{}\"", tier, site.key);
+            }
+            sites.insert(site.key.clone(), site);
+        }
+
+        let mut accessories = AccessoryIndex::new();
+        for (tier, entry) in raw.accessories.into_iter().enumerate() {
+            let acc = parse_accessory(entry, tier, &telescopes).with_context(|| format!("accessories[{}]", tier))?;
+            if accessories.contains_key(&acc.key) {
+                bail!("accessories[{}]: there's already an accessory with key \"{}\"", tier, acc.key);
+            }
+            accessories.insert(acc.key.clone(), acc);
+        }
+        let provided: HashSet<String> = accessories.values().flat_map(|acc| acc.provides.iter().cloned()).collect();
+
+        let mut keys = HashSet::new();
+        let mut objects = vec![];
+        for (i, entry) in raw.objects.into_iter().enumerate() {
+            let obj = parse_object(entry, &provided, &observer).with_context(|| format!("objects[{}]", i))?;
+            if !keys.insert(obj.key.clone()) {
+                bail!("objects[{}]: there's already an object with key \"{}\"", i, obj.key);
+            }
+            if obj.key.starts_with(deep_field::KEY_PREFIX) {
+                bail!("objects[{}]: \"{}\" starts with \"{}\", which is for the deep field", i, obj.key, deep_field::KEY_PREFIX);
+            }
+            objects.push(obj);
+        }
+
+        let mut events: Vec<EventKind> = vec![];
+        for (i, entry) in raw.events.into_iter().enumerate() {
+            let kind = parse_event(entry, &provided).with_context(|| format!("events[{}]", i))?;
+            if events.iter().any(|other| other.key == kind.key) || keys.contains(&kind.key) {
+                bail!("events[{}]: there's already something with key \"{}\"", i, kind.key);
+            }
+            // Each visit's key is the event's key and its slot, like "supernova_3"
+            let prefix = format!("{}_", kind.key);
+            if let Some(key) = keys.iter().find(|key| key.starts_with(&prefix)) {
+                bail!("events[{}]: \"{}\" visits get keys like \"{}0\", so there can't be an object with key \"{}\"", i, kind.key, prefix, key);
+            }
+            if kind.key.starts_with(deep_field::KEY_PREFIX) {
+                bail!("events[{}]: \"{}\" starts with \"{}\", which is for the deep field", i, kind.key, deep_field::KEY_PREFIX);
+            }
+            events.push(kind);
+        }
+
+        Ok(Self { objects, telescopes, sites, accessories, events, view, observer })
+    }
+}
+
+fn parse_view(view: &RawView, display: &RawDisplay) -> Result<SkyView> {
+    if !(view.fov > 0.0 && view.fov < 180.0) {
+        bail!("fov should be between 0 and 180 degrees, not {}", view.fov);
+    }
+    if display.width <= 0.0 || display.height <= 0.0 {
+        bail!("the display needs a positive size, not {}x{}", display.width, display.height);
+    }
+    let projection = match view.projection.as_deref() {
+        None | Some("stereographic") => Projection::Stereographic,
+        Some("gnomonic") => Projection::Gnomonic,
+        Some("orthographic") => Projection::Orthographic,
+        Some(other) => bail!("unknown projection \"{}\" (expected stereographic, gnomonic or orthographic)", other)
+    };
+    if !(-90.0..=90.0).contains(&view.alt) {
+        bail!("alt should be between -90 and 90 degrees, not {}", view.alt);
+    }
+    Ok(SkyView {
+        center: sky::horizontal(view.alt.to_radians(), view.az.to_radians()),
+        fov: view.fov.to_radians(),
+        width: display.width,
+        height: display.height,
+        projection
+    })
+}
+
+fn parse_observer(observer: &RawObserver) -> Result<Observer> {
+    if !(-90.0..=90.0).contains(&observer.latitude) {
+        bail!("latitude should be between -90 and 90 degrees, not {}", observer.latitude);
+    }
+    let day = match &observer.date {
+        Some(date) => orbits::day_number(date)?,
+        None => orbits::day_number("2000-01-01")?
+    };
+    Ok(Observer::new(observer.latitude.to_radians(), observer.lst.to_radians(), day))
+}
+
+fn parse_coords(ra: f64, dec: f64) -> Result<SkyCoord> {
+    if !(0.0..360.0).contains(&ra) {
+        bail!("ra should be between 0 and 360 degrees, not {}", ra);
+    }
+    if !(-90.0..=90.0).contains(&dec) {
+        bail!("dec should be between -90 and 90 degrees, not {}", dec);
+    }
+    Ok(SkyCoord::degrees(ra, dec))
+}
+
+fn parse_telescope(entry: Value, tier: usize) -> Result<Telescope> {
+    let raw: RawTelescope = serde_json::from_value(entry)?;
+    if raw.max_power <= 0 {
+        bail!("\"{}\" needs a positive max_power, not {}", raw.key, raw.max_power);
+    }
+    let weather_sensitivity = raw.weather_sensitivity.unwrap_or(1.0);
+    if !(0.0..=1.0).contains(&weather_sensitivity) {
+        bail!("\"{}\" needs a weather_sensitivity between 0 and 1, not {}", raw.key, weather_sensitivity);
+    }
+    if !(raw.fov > 0.0 && raw.fov < 180.0) {
+        bail!("\"{}\" should have a fov between 0 and 180 degrees, not {}", raw.key, raw.fov);
+    }
+    if raw.slew_speed <= 0.0 {
+        bail!("\"{}\" needs a positive slew_speed, not {}", raw.key, raw.slew_speed);
+    }
+    let image = match raw.image {
+        Some(image) => image,
+        None => format!("./assets/telescopes/{}.png", raw.key)
+    };
+    Ok(Telescope {
+        key: raw.key,
+        name: raw.name,
+        description: raw.description,
+        max_power: raw.max_power,
+        tier,
+        unlock: raw.unlock,
+        price: raw.price,
+        weather_sensitivity,
+        fov: raw.fov.to_radians(),
+        slew_speed: raw.slew_speed.to_radians(),
+        image
+    })
+}
+
+fn parse_site(entry: Value, tier: usize) -> Result<Site> {
+    let raw: RawSite = serde_json::from_value(entry)?;
+    if !(1..=9).contains(&raw.bortle) {
+        bail!("\"{}\" needs a bortle class from 1 to 9, not {}", raw.key, raw.bortle);
+    }
+    Ok(Site {
+        key: raw.key,
+        name: raw.name,
+        description: raw.description,
+        bortle: raw.bortle,
+        sky_color: raw.sky_color,
+        tier,
+        unlock: raw.unlock,
+        price: raw.price
+    })
+}
+
+// Accessories have to fit telescopes that exist, and only eyepieces have a field of view
+fn parse_accessory(entry: Value, tier: usize, telescopes: &TelescopeIndex) -> Result<Accessory> {
+    let raw: RawAccessory = serde_json::from_value(entry)?;
+    let kind = raw.kind.parse::<AccessoryKind>()
+        .with_context(|| format!("\"{}\"", raw.key))?;
+
+    if raw.fits.is_empty() {
+        bail!("\"{}\" doesn't fit any telescopes", raw.key);
+    }
+    if let Some(scope) = raw.fits.iter().find(|scope| !telescopes.contains_key(scope.as_str())) {
+        bail!("\"{}\" fits \"{}\", but there's no such telescope", raw.key, scope);
+    }
+    if raw.power_bonus < 0 {
+        bail!("\"{}\" needs a power_bonus of at least 0, not {}", raw.key, raw.power_bonus);
+    }
+    let fov = match (kind, raw.fov) {
+        (AccessoryKind::Eyepiece, Some(fov)) if fov > 0.0 && fov < 180.0 => Some(fov.to_radians()),
+        (AccessoryKind::Eyepiece, Some(fov)) => bail!("\"{}\" should have a fov between 0 and 180 degrees, not {}", raw.key, fov),
+        (AccessoryKind::Eyepiece, None) => bail!("\"{}\" is an eyepiece, so it needs a fov", raw.key),
+        (_, Some(_)) => bail!("\"{}\" isn't an eyepiece, so it can't have a fov", raw.key),
+        (_, None) => None,
+    };
+
+    Ok(Accessory {
+        key: raw.key,
+        name: raw.name,
+        description: raw.description,
+        kind,
+        power_bonus: raw.power_bonus,
+        fov,
+        provides: raw.provides,
+        fits: raw.fits,
+        tier,
+        unlock: raw.unlock,
+        price: raw.price
+    })
+}
+
+fn parse_object(entry: Value, provided: &HashSet<String>, observer: &Observer) -> Result<AstroObject> {
+    let raw: RawObject = serde_json::from_value(entry)?;
+    let key = match raw.key {
+        Some(key) => key,
+        None => key_from_name(&raw.name)
+    };
+    if key.is_empty() {
+        bail!("\"{}\" needs a key, or a name with some letters or numbers in it", raw.name);
+    }
+    let category = raw.category.parse::<AstroObjectCategory>()
+        .with_context(|| format!("\"{}\"", key))?;
+
+    // Transient things come and go on their own, so they can't be part of the permanent sky
+    let behavior = category.behavior();
+    if behavior.is_transient() {
+        bail!("\"{}\" is a {}, which only comes around for a while", key, behavior.name());
+    }
+    if (raw.motion.is_some() || raw.orbit.is_some()) && !behavior.moves() {
+        bail!("\"{}\" is a {}, which doesn't move", key, behavior.name());
+    }
+
+    // Things with an orbit are wherever it puts them when the game starts
+    let orbit = match raw.orbit {
+        Some(_) if raw.motion.is_some() || raw.ra.is_some() || raw.dec.is_some() => {
+            bail!("\"{}\" has an orbit, so it can't have an ra, dec or motion too", key)
+        }
+        Some(body) => Some(body.parse::<Body>().with_context(|| format!("\"{}\"", key))?),
+        None => None
+    };
+    let coords = match (orbit, raw.ra, raw.dec) {
+        (Some(body), _, _) => orbits::position(body, observer.day),
+        (None, Some(ra), Some(dec)) => parse_coords(ra, dec).with_context(|| format!("\"{}\"", key))?,
+        (None, _, _) => bail!("\"{}\" needs an ra and dec, or an orbit", key)
+    };
+    let motion = match raw.motion {
+        Some(motion) => Some(Motion::Drift {
+            from: coords,
+            since: 0,
+            ra_per_tick: (motion.ra * sky::SKY_DAYS_PER_TICK).to_radians(),
+            dec_per_tick: (motion.dec * sky::SKY_DAYS_PER_TICK).to_radians()
+        }),
+        None => orbit.map(|body| Motion::Orbit { body, epoch: observer.day })
+    };
+
+    let detail = parse_detail(&key, raw.detail, provided)?;
+
+    Ok(AstroObject {
+        power_needed: detail[0].power_needed,
+        key,
+        name: raw.name,
+        category,
+        coords,
+        magnitude: raw.magnitude,
+        distance_ly: raw.distance_ly,
+        motion,
+        detail
+    })
+}
+
+// Events have to be transient, and only things that move can have a speed
+fn parse_event(entry: Value, provided: &HashSet<String>) -> Result<EventKind> {
+    let raw: RawEvent = serde_json::from_value(entry)?;
+    let key = raw.key;
+    let category = raw.category.parse::<AstroObjectCategory>()
+        .with_context(|| format!("\"{}\"", key))?;
+    let behavior = category.behavior();
+    if !behavior.is_transient() {
+        bail!("\"{}\" is a {}, which is around all the time, so it can't be an event", key, behavior.name());
+    }
+    if raw.every <= 0 || raw.duration <= 0 || raw.duration > raw.every {
+        bail!("\"{}\" needs a positive duration that fits in every, not {} in {}", key, raw.duration, raw.every);
+    }
+    if !(0.0..=1.0).contains(&raw.chance) {
+        bail!("\"{}\" needs a chance between 0 and 1, not {}", key, raw.chance);
+    }
+    let coords = match (raw.ra, raw.dec) {
+        (Some(ra), Some(dec)) => Some(parse_coords(ra, dec).with_context(|| format!("\"{}\"", key))?),
+        (None, None) => None,
+        _ => bail!("\"{}\" needs both ra and dec, or neither", key)
+    };
+    let speed = match raw.speed {
+        Some(_) if !behavior.moves() => bail!("\"{}\" is a {}, which doesn't move", key, behavior.name()),
+        Some(speed) if speed < 0.0 => bail!("\"{}\" can't have a negative speed", key),
+        Some(speed) => (speed * sky::SKY_DAYS_PER_TICK).to_radians(),
+        None => 0.0
+    };
+    let detail = parse_detail(&key, raw.detail, provided)?;
+
+    Ok(EventKind {
+        key,
+        name: raw.name,
+        category,
+        every: raw.every,
+        chance: raw.chance,
+        duration: raw.duration,
+        bonus: raw.bonus,
+        coords,
+        speed,
+        detail
+    })
+}
+
+// A level of detail can only require something that some accessory provides
+fn parse_detail(key: &str, raw: Vec<RawDetail>, provided: &HashSet<String>) -> Result<Vec<AstroDetail>> {
+    if raw.is_empty() {
+        bail!("\"{}\" doesn't have any levels of detail", key);
+    }
+
+    let mut detail = vec![];
+    for (i, raw_detail) in raw.into_iter().enumerate() {
+        // Levels are numbered from 1 in the JSON, and from 0 in the game
+        if raw_detail.level != i + 1 {
+            bail!("\"{}\": detail[{}] should be level {}, not {}", key, i, i + 1, raw_detail.level);
+        }
+        let power_needed = match (raw_detail.power_needed, raw_detail.magnitude) {
+            (Some(power), None) => power,
+            (None, Some(magnitude)) => power_for_magnitude(magnitude),
+            (Some(_), Some(_)) => bail!("\"{}\": detail[{}] has both power_needed and magnitude; pick one", key, i),
+            (None, None) => bail!("\"{}\": detail[{}] needs either power_needed or magnitude", key, i),
+        };
+        if let Some(thing) = raw_detail.requires.iter().find(|thing| !provided.contains(thing.as_str())) {
+            bail!("\"{}\": detail[{}] requires \"{}\", but no accessory provides it", key, i, thing);
+        }
+        detail.push(AstroDetail {
+            level: i,
+            power_needed,
+            requires: raw_detail.requires,
+            discovery_text: raw_detail.description
+        });
+    }
+    Ok(detail)
+}
+
+/// The resolving power needed to see something of the given apparent magnitude.
+/// Tuned so that Sirius (-1.46) needs 1, and Sirius B (8.44) needs 10.
+pub fn power_for_magnitude(magnitude: f64) -> i32 {
+    cmp::max(1, (magnitude + 1.5).ceil() as i32)
+}
+
+/// The other way around: the faintest magnitude a resolving power can make out
+pub fn magnitude_for_power(power: i32) -> f64 {
+    power as f64 - 1.5
+}
+
+// "M31 Andromeda Galaxy" => "m31_andromeda_galaxy"
+fn key_from_name(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<String>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // A small but complete document, to break in different ways
+    fn document() -> Value {
+        json!({
+            "display": { "width": 800, "height": 600 },
+            "view": { "az": 180, "alt": 45, "fov": 90 },
+            "observer": { "latitude": 40, "lst": 0, "date": "2025-09-01" },
+            "telescopes": [
+                { "key": "eye", "name": "Your eye", "max_power": 4, "fov": 90, "slew_speed": 90 },
+                {
+                    "key": "binoculars", "name": "Binoculars", "max_power": 6, "fov": 6, "slew_speed": 45,
+                    "unlock": [{ "objects_observed": 2 }], "price": 50
+                }
+            ],
+            "sites": [
+                { "key": "backyard", "name": "Your backyard", "bortle": 7, "sky_color": "#102040" }
+            ],
+            "accessories": [
+                {
+                    "key": "h_alpha_filter", "name": "H-alpha filter", "kind": "filter",
+                    "provides": ["h_alpha"], "fits": ["binoculars"], "price": 20
+                }
+            ],
+            "objects": [
+                {
+                    "key": "sirius", "name": "Sirius", "category": "star", "ra": 101.3, "dec": -16.7,
+                    "detail": [{ "level": 1, "magnitude": -1.46, "description": "So bright!" }]
+                },
+                {
+                    "name": "M42 Orion Nebula", "category": "nebula", "ra": 83.8, "dec": -5.4,
+                    "detail": [
+                        { "level": 1, "power_needed": 4, "description": "A fuzzy patch." },
+                        { "level": 2, "power_needed": 6, "requires": ["h_alpha"], "description": "It glows red." }
+                    ]
+                },
+                {
+                    "key": "moon", "name": "The Moon", "category": "moon", "orbit": "moon",
+                    "detail": [{ "level": 1, "magnitude": -12.7, "description": "Craters!" }]
+                }
+            ],
+            "events": [
+                {
+                    "key": "supernova", "name": "Supernova", "category": "supernova",
+                    "every": 100, "duration": 50, "bonus": 10,
+                    "detail": [{ "level": 1, "power_needed": 6, "description": "A new star!" }]
+                }
+            ]
+        })
+    }
+
+    fn load(document: &Value) -> Result<GameData> {
+        GameData::from_json(&document.to_string())
+    }
+
+    // The whole chain of context for an error, like "objects[0]: ..."
+    fn error(document: &Value) -> String {
+        let e = load(document).err().expect("expected the document not to load");
+        format!("{:#}", e)
+    }
+
+    #[test]
+    fn load_a_good_document() {
+        let data = load(&document()).unwrap();
+        assert_eq!(data.telescopes.len(), 2);
+        let binoculars = &data.telescopes["binoculars"];
+        assert_eq!(binoculars.tier, 1);
+        assert_eq!(binoculars.unlock, vec![Unlock::ObjectsObserved(2)]);
+        assert_eq!(binoculars.image, "./assets/telescopes/binoculars.png");
+        assert_eq!(data.telescopes["eye"].weather_sensitivity, 1.0);
+        assert_eq!(data.sites["backyard"].bortle, 7);
+        assert_eq!(data.accessories["h_alpha_filter"].kind, AccessoryKind::Filter);
+        assert_eq!(data.events.len(), 1);
+
+        let keys: Vec<&str> = data.objects.iter().map(|obj| obj.key.as_str()).collect();
+        assert_eq!(keys, vec!["sirius", "m42_orion_nebula", "moon"]);
+        let sirius = &data.objects[0];
+        assert_eq!(sirius.power_needed, 1);
+        assert!(sirius.motion.is_none());
+        let m42 = &data.objects[1];
+        assert_eq!(m42.detail.len(), 2);
+        assert_eq!(m42.detail[1].level, 1);
+        assert_eq!(m42.detail[1].requires, vec!["h_alpha".to_string()]);
+
+        // The Moon is wherever its orbit puts it on the day the game starts
+        let moon = &data.objects[2];
+        assert_eq!(moon.coords, orbits::position(Body::Moon, data.observer.day));
+        assert!(matches!(moon.motion, Some(Motion::Orbit { body: Body::Moon, .. })));
+    }
+
+    #[test]
+    fn bad_category() {
+        let mut doc = document();
+        doc["objects"][0]["category"] = json!("asteroid");
+        let e = error(&doc);
+        assert!(e.starts_with("objects[0]: \"sirius\": unknown category \"asteroid\""), "{}", e);
+
+        // Transient categories only belong in events, and the other way around
+        let mut doc = document();
+        doc["objects"][0]["category"] = json!("supernova");
+        assert!(error(&doc).contains("only comes around for a while"));
+        let mut doc = document();
+        doc["events"][0]["category"] = json!("star");
+        assert!(error(&doc).starts_with("events[0]: \"supernova\" is a star"));
+    }
+
+    #[test]
+    fn unknown_key() {
+        let mut doc = document();
+        doc["accessories"][0]["fits"] = json!(["refractor"]);
+        let e = error(&doc);
+        assert_eq!(e, "accessories[0]: \"h_alpha_filter\" fits \"refractor\", but there's no such telescope");
+
+        let mut doc = document();
+        doc["objects"][1]["detail"][1]["requires"] = json!(["oiii"]);
+        let e = error(&doc);
+        assert!(e.contains("requires \"oiii\", but no accessory provides it"), "{}", e);
+
+        let mut doc = document();
+        doc["objects"][2]["orbit"] = json!("pluto");
+        assert!(error(&doc).starts_with("objects[2]: \"moon\""));
+    }
+
+    #[test]
+    fn duplicate_key() {
+        let mut doc = document();
+        doc["telescopes"][1]["key"] = json!("eye");
+        assert_eq!(error(&doc), "telescopes[1]: there's already a telescope with key \"eye\"");
+
+        // A key made from a name can clash too
+        let mut doc = document();
+        doc["objects"][0]["key"] = json!("m42_orion_nebula");
+        assert_eq!(error(&doc), "objects[1]: there's already an object with key \"m42_orion_nebula\"");
+
+        // Events share keys with objects
+        let mut doc = document();
+        doc["events"][0]["key"] = json!("sirius");
+        assert_eq!(error(&doc), "events[0]: there's already something with key \"sirius\"");
+
+        // Generated keys can't clash with the game data's either
+        let mut doc = document();
+        doc["objects"][0]["key"] = json!("supernova_2");
+        assert_eq!(error(&doc), "events[0]: \"supernova\" visits get keys like \"supernova_0\", so there can't be an object with key \"supernova_2\"");
+        let mut doc = document();
+        doc["objects"][0]["key"] = json!("df_0_0_1");
+        assert_eq!(error(&doc), "objects[0]: \"df_0_0_1\" starts with \"df_\", which is for the deep field");
+        let mut doc = document();
+        doc["events"][0]["key"] = json!("df_nova");
+        assert!(error(&doc).starts_with("events[0]: \"df_nova\" starts with \"df_\""));
+    }
+
+    #[test]
+    fn malformed_entries() {
+        assert!(error(&json!("not game data")).starts_with("game data isn't valid JSON"));
+
+        let mut doc = document();
+        doc["telescopes"][0].as_object_mut().unwrap().remove("max_power");
+        let e = error(&doc);
+        assert!(e.starts_with("telescopes[0]: missing field `max_power`"), "{}", e);
+
+        let mut doc = document();
+        doc["objects"][0]["ra"] = json!(400);
+        assert!(error(&doc).contains("ra should be between 0 and 360 degrees, not 400"));
+
+        let mut doc = document();
+        doc["objects"][0].as_object_mut().unwrap().remove("dec");
+        assert!(error(&doc).contains("\"sirius\" needs an ra and dec, or an orbit"));
+
+        let mut doc = document();
+        doc["objects"][1]["detail"][1]["level"] = json!(3);
+        assert!(error(&doc).contains("detail[1] should be level 2, not 3"));
+
+        let mut doc = document();
+        doc["objects"][1]["detail"][0]["magnitude"] = json!(4.0);
+        assert!(error(&doc).contains("has both power_needed and magnitude"));
+
+        let mut doc = document();
+        doc["objects"][0]["detail"] = json!([]);
+        assert!(error(&doc).contains("doesn't have any levels of detail"));
+
+        let mut doc = document();
+        doc["sites"][0]["bortle"] = json!(0);
+        assert!(error(&doc).starts_with("sites[0]: \"backyard\" needs a bortle class from 1 to 9"));
+
+        let mut doc = document();
+        doc["accessories"][0]["fov"] = json!(1.0);
+        assert!(error(&doc).contains("isn't an eyepiece, so it can't have a fov"));
+
+        let mut doc = document();
+        doc["events"][0]["duration"] = json!(200);
+        assert!(error(&doc).contains("needs a positive duration that fits in every"));
+
+        let mut doc = document();
+        doc["view"]["projection"] = json!("fisheye");
+        assert!(error(&doc).starts_with("view: unknown projection \"fisheye\""));
+    }
+
+    #[test]
+    fn keys_from_names() {
+        assert_eq!(key_from_name("Sirius"), "sirius");
+        assert_eq!(key_from_name("M31 Andromeda Galaxy"), "m31_andromeda_galaxy");
+        assert_eq!(key_from_name("  Barnard's   Star "), "barnard_s_star");
+        assert_eq!(key_from_name("NGC 6543 (Cat's Eye)"), "ngc_6543_cat_s_eye");
+        assert_eq!(key_from_name("???"), "");
+
+        // A name like that can't make a key
+        let mut doc = document();
+        doc["objects"][1]["name"] = json!("???");
+        assert_eq!(error(&doc), "objects[1]: \"???\" needs a key, or a name with some letters or numbers in it");
+        let mut doc = document();
+        doc["objects"][0]["key"] = json!("");
+        assert!(error(&doc).starts_with("objects[0]: \"Sirius\" needs a key"));
+    }
+
+    #[test]
+    fn powers_for_magnitudes() {
+        assert_eq!(power_for_magnitude(-12.7), 1); // the Moon
+        assert_eq!(power_for_magnitude(-1.46), 1); // Sirius
+        assert_eq!(power_for_magnitude(-0.5), 1);
+        assert_eq!(power_for_magnitude(3.4), 5); // M31
+        assert_eq!(power_for_magnitude(6.5), 8);
+        assert_eq!(power_for_magnitude(8.44), 10); // Sirius B
+        assert_eq!(magnitude_for_power(10), 8.5);
+        assert_eq!(power_for_magnitude(magnitude_for_power(7)), 7);
+    }
+}
@@ -0,0 +1,93 @@
+// Whatever the game core needs from the outside world (somewhere to log to, the time)
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub trait Logger {
+    fn log(&self, message: &str);
+}
+
+/// Wall-clock time, in milliseconds since the Unix epoch
+pub trait Clock {
+    fn now_ms(&self) -> f64;
+}
+
+pub struct Platform {
+    pub logger: Box<dyn Logger>,
+    pub clock: Box<dyn Clock>
+}
+
+impl Platform {
+    pub fn new(logger: Box<dyn Logger>, clock: Box<dyn Clock>) -> Self {
+        Self { logger, clock }
+    }
+
+    /// Print to stdout and use the system clock
+    pub fn native() -> Self {
+        Self::new(Box::new(StdoutLogger), Box::new(SystemClock))
+    }
+
+    /// No logging, and a clock that only moves when told to. Good for tests
+    pub fn headless() -> Self {
+        Self::new(Box::new(SilentLogger), Box::new(ManualClock::new(0.0)))
+    }
+}
+
+// A macro to provide `println!(..)`-style syntax for logging through a Logger.
+#[macro_export]
+macro_rules! log_to {
+    ( $logger:expr, $( $t:tt )* ) => {
+        $logger.log(&format!( $( $t )* ))
+    }
+}
+
+pub struct StdoutLogger;
+
+impl Logger for StdoutLogger {
+    fn log(&self, message: &str) {
+        println!("{}", message);
+    }
+}
+
+pub struct SilentLogger;
+
+impl Logger for SilentLogger {
+    fn log(&self, _message: &str) {}
+}
+
+/// Doesn't work in wasm; the web front end has its own clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> f64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_secs_f64() * 1000.0).unwrap_or(0.0)
+    }
+}
+
+pub struct ManualClock {
+    now_ms: Cell<f64>
+}
+
+impl ManualClock {
+    pub fn new(now_ms: f64) -> Self {
+        Self { now_ms: Cell::new(now_ms) }
+    }
+
+    pub fn advance(&self, ms: f64) {
+        self.now_ms.set(self.now_ms.get() + ms);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&self) -> f64 {
+        self.now_ms.get()
+    }
+}
+
+// So a test can hold on to a clock it's handed to the game
+impl<C: Clock> Clock for Rc<C> {
+    fn now_ms(&self) -> f64 {
+        (**self).now_ms()
+    }
+}
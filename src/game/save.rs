@@ -0,0 +1,286 @@
+// Versioned JSON saves, with migrations for older versions. A save only stores what
+// can't be rebuilt: anything worked out from the seed and the generation isn't saved.
+
+use anyhow::*;
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use super::journal::Journal;
+
+pub const SAVE_VERSION: u64 = 10;
+pub const SAVE_KEY: &str = "deepfield-save";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveGame {
+    pub version: u64,
+    pub generation: i32,
+
+    // The game's seed, which decides things like what the sky looks like
+    pub seed: u64,
+
+    // SmallRng can't be serialized, so the game reseeds its RNG from this when saving
+    pub rng_seed: u64,
+
+    // When the game was saved, in ms since the Unix epoch (0 if we don't know)
+    pub saved_at_ms: f64,
+
+    // Keys of the telescopes the player has
+    pub telescopes: Vec<String>,
+
+    // Key of the telescope the player's using
+    pub active: Option<String>,
+
+    // Keys of the observing sites the player has
+    pub sites: Vec<String>,
+
+    // Keys of the player's eyepieces, filters and cameras
+    pub accessories: Vec<String>,
+
+    // Keys of the observed objects, and the detail level they were observed at
+    pub observed: BTreeMap<String, usize>,
+
+    // Keys of the deep field's sky regions, and how many layers of each have been generated
+    pub explored: BTreeMap<String, u32>,
+
+    // Every observation the player's made. These are kept whole, not as keys
+    pub journal: Journal,
+
+    // Where the telescope's pointed, as (az, alt) in radians, and what it's turning toward.
+    // Saves from before telescopes had to turn don't have these
+    pub pointing: Option<(f64, f64)>,
+    pub slew: Option<SavedSlew>,
+
+    pub photons: u64,
+    pub photons_earned: u64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedSlew {
+    pub target: String, // the object's key
+    pub arrives: i32
+}
+
+// MIGRATIONS[i] upgrades a save from version i + 1 to version i + 2
+type Migration = fn(&mut Map<String, Value>);
+const MIGRATIONS: &[Migration] = &[
+    migrate_v1_seed,
+    migrate_v2_saved_at,
+    migrate_v3_photons,
+    migrate_v4_sites,
+    migrate_v5_accessories,
+    migrate_v6_active,
+    migrate_v7_explored,
+    migrate_v8_journal,
+    migrate_v9_pointing,
+];
+
+// Version 1 didn't have a seed (the sky was different every time you loaded).
+// Reusing the RNG seed gives it a stable one from now on.
+fn migrate_v1_seed(save: &mut Map<String, Value>) {
+    let seed = save.get("rng_seed").cloned().unwrap_or_else(|| 0.into());
+    save.insert("seed".to_string(), seed);
+}
+
+// Version 2 didn't record when it was saved
+fn migrate_v2_saved_at(save: &mut Map<String, Value>) {
+    save.insert("saved_at_ms".to_string(), 0.0.into());
+}
+
+// Version 3 was from before photons existed
+fn migrate_v3_photons(save: &mut Map<String, Value>) {
+    save.insert("photons".to_string(), 0.into());
+    save.insert("photons_earned".to_string(), 0.into());
+}
+
+// Version 4 was from before observing sites. Free sites get handed out when the
+// game's restored, so these players end up in the backyard like everyone else
+fn migrate_v4_sites(save: &mut Map<String, Value>) {
+    save.insert("sites".to_string(), Value::Array(vec![]));
+}
+
+// Version 5 was from before accessories
+fn migrate_v5_accessories(save: &mut Map<String, Value>) {
+    save.insert("accessories".to_string(), Value::Array(vec![]));
+}
+
+// Version 6 didn't say which telescope was in use. Without one, the restored
+// game picks the most powerful
+fn migrate_v6_active(save: &mut Map<String, Value>) {
+    save.insert("active".to_string(), Value::Null);
+}
+
+// Version 7 was from before the deep field
+fn migrate_v7_explored(save: &mut Map<String, Value>) {
+    save.insert("explored".to_string(), Value::Object(Map::new()));
+}
+
+// Version 8 didn't keep a journal. Whatever the player had observed back then
+// is still observed, it just isn't written up anywhere
+fn migrate_v8_journal(save: &mut Map<String, Value>) {
+    save.insert("journal".to_string(), Value::Array(vec![]));
+}
+
+// Version 9 forgot where the telescope was pointed. It starts over from the default view
+fn migrate_v9_pointing(save: &mut Map<String, Value>) {
+    save.insert("pointing".to_string(), Value::Null);
+    save.insert("slew".to_string(), Value::Null);
+}
+
+impl SaveGame {
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).context("SaveGame::to_json: couldn't serialize the game")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json).context("SaveGame::from_json: the save isn't valid JSON")?;
+        let value = migrate(value)?;
+        serde_json::from_value(value).context("SaveGame::from_json: the save is missing something")
+    }
+}
+
+/// Bring a save from any older version up to SAVE_VERSION
+pub fn migrate(mut value: Value) -> Result<Value> {
+    let save = value.as_object_mut().context("migrate: the save should be a JSON object")?;
+    let mut version = save.get("version")
+        .and_then(|v| v.as_u64())
+        .context("migrate: the save doesn't say what version it is")?;
+
+    if version == 0 || version > SAVE_VERSION {
+        bail!("migrate: don't know how to load a version {} save (the current version is {})", version, SAVE_VERSION);
+    }
+
+    while version < SAVE_VERSION {
+        MIGRATIONS[version as usize - 1](save);
+        version += 1;
+        save.insert("version".to_string(), version.into());
+    }
+
+    Ok(value)
+}
+
+/// Somewhere to keep saved games
+pub trait Storage {
+    fn load(&self, key: &str) -> Result<Option<String>>;
+    fn store(&mut self, key: &str, value: &str) -> Result<()>;
+}
+
+/// Storage that only lasts as long as the program does. Handy for tests
+#[derive(Default)]
+pub struct MemoryStorage {
+    items: HashMap<String, String>
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.items.get(key).cloned())
+    }
+
+    fn store(&mut self, key: &str, value: &str) -> Result<()> {
+        self.items.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::game::platform::Platform;
+
+    // A save from the very first version, before seeds or photons
+    const V1_SAVE: &str = r#"{
+        "version": 1,
+        "generation": 42,
+        "rng_seed": 1234,
+        "telescopes": ["eye", "refractor_2in"],
+        "observed": { "sirius": 0, "m31": 1 }
+    }"#;
+
+    // One from partway along, with photons and sites but no accessories yet
+    const V5_SAVE: &str = r#"{
+        "version": 5,
+        "generation": 300,
+        "seed": 99,
+        "rng_seed": 5678,
+        "saved_at_ms": 1700000000000.0,
+        "telescopes": ["eye", "refractor_2in"],
+        "sites": ["backyard"],
+        "observed": { "sirius": 0, "m31": 2, "m42": 1 },
+        "photons": 250,
+        "photons_earned": 1200
+    }"#;
+
+    fn observed_keys(game: &Game) -> BTreeMap<String, usize> {
+        game.state.observed.iter().map(|(obj, level)| (obj.key.clone(), *level)).collect()
+    }
+
+    #[test]
+    fn migrate_a_v1_save() {
+        let save = SaveGame::from_json(V1_SAVE).unwrap();
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.seed, 1234); // from the RNG seed
+        assert_eq!(save.generation, 42);
+        assert_eq!(save.photons, 0);
+        assert!(save.accessories.is_empty() && save.explored.is_empty() && save.journal.is_empty());
+        assert_eq!((save.pointing, save.slew.clone()), (None, None));
+
+        let game = Game::restore(save.clone(), Platform::headless());
+        assert_eq!(observed_keys(&game), save.observed);
+        assert_eq!(game.generation, 42);
+        assert_eq!(game.state.telescope().unwrap().key, "refractor_2in");
+        assert_eq!(game.state.site().unwrap().key, "backyard");
+    }
+
+    #[test]
+    fn migrate_a_v5_save() {
+        let save = SaveGame::from_json(V5_SAVE).unwrap();
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.seed, 99);
+        assert_eq!(save.saved_at_ms, 1700000000000.0);
+        assert_eq!(save.active, None);
+
+        let mut game = Game::restore(save.clone(), Platform::headless());
+        assert_eq!(observed_keys(&game), save.observed);
+        assert_eq!(game.state.photons, 250);
+        assert_eq!(game.state.photons_earned, 1200);
+
+        // And it saves as the current version, with nothing lost
+        let resaved = game.save();
+        assert_eq!(resaved.version, SAVE_VERSION);
+        assert_eq!(resaved.observed, save.observed);
+        assert_eq!((resaved.photons, resaved.photons_earned), (250, 1200));
+    }
+
+    #[test]
+    fn every_version_migrates() {
+        // Each version's save is the one before with its migration applied, so
+        // starting from any of them ends up in the same place
+        let mut value: Value = serde_json::from_str(V1_SAVE).unwrap();
+        for version in 1..=SAVE_VERSION {
+            let mut save = value.clone();
+            save["version"] = version.into();
+            let save: SaveGame = serde_json::from_value(migrate(save).unwrap()).unwrap();
+            assert_eq!(save.version, SAVE_VERSION);
+            assert_eq!(save.observed.len(), 2);
+            if version < SAVE_VERSION {
+                MIGRATIONS[version as usize - 1](value.as_object_mut().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn refuse_saves_from_the_future() {
+        let json = V1_SAVE.replace("\"version\": 1", &format!("\"version\": {}", SAVE_VERSION + 1));
+        let e = SaveGame::from_json(&json).unwrap_err();
+        assert!(format!("{:#}", e).contains(&format!("don't know how to load a version {} save", SAVE_VERSION + 1)));
+
+        let json = V1_SAVE.replace("\"version\": 1", "\"version\": 0");
+        assert!(SaveGame::from_json(&json).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+// Every bit of randomness in a game comes from its seed, through a stream per part
+// of the game, so adding randomness in one place doesn't change it everywhere else.
+
+use rand::prelude::*;
+use rand::rngs::SmallRng;
+
+// Names of the random streams
+pub const STARFIELD: &str = "starfield";
+pub const OBSERVATIONS: &str = "observations";
+pub const CLOUDS: &str = "clouds";
+pub const SEEING: &str = "seeing";
+pub const HUMIDITY: &str = "humidity";
+pub const CLOUD_SHAPES: &str = "cloud_shapes";
+pub const FIELD: &str = "field";
+pub const DEEP_FIELD: &str = "deep_field";
+pub const EVENTS: &str = "events";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Seed(pub u64);
+
+impl Seed {
+    pub fn from_entropy() -> Self {
+        Seed(SmallRng::from_entropy().gen())
+    }
+
+    /// An independent random stream, named after whatever it's used for
+    pub fn stream(&self, name: &str) -> SmallRng {
+        SmallRng::seed_from_u64(self.derive(name))
+    }
+
+    /// Mix the stream name into the seed. This needs to give the same answer on
+    /// every platform and Rust version, so it doesn't use std's Hasher.
+    pub fn derive(&self, name: &str) -> u64 {
+        // FNV-1a for the name...
+        let hash = name.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        // ...then a splitmix64 finalizer to spread it out
+        let mut z = self.0 ^ hash;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
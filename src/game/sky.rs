@@ -0,0 +1,153 @@
+// The observer's sky: where they are on Earth, and the time and date there, all
+// worked out from the generation. The clock runs fast, so objects rise and set.
+
+use std::f64::consts;
+use crate::points::{SkyCoord, mod360};
+use crate::orbits::{self, Body, Phase};
+
+// How much sky time passes each tick
+pub const SKY_MINUTES_PER_TICK: f64 = 2.0;
+pub const SKY_DAYS_PER_TICK: f64 = SKY_MINUTES_PER_TICK / (24.0 * 60.0);
+
+// The stars come back around a little faster than the sun does: 360deg of
+// sidereal time takes 23h56m
+const SIDEREAL_DEGREES_PER_MINUTE: f64 = 360.985_647 / (24.0 * 60.0);
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Observer {
+    pub latitude: f64, // radians, north is positive
+    pub lst: f64, // local sidereal time, as an angle (radians): the ra that's due south
+    pub day: f64, // the date, as a day number (see orbits)
+}
+
+impl Observer {
+    pub fn new(latitude: f64, lst: f64, day: f64) -> Self {
+        Self { latitude, lst: mod360(lst), day }
+    }
+
+    /// Let some ticks' worth of time go by
+    pub fn advance(&mut self, ticks: i32) {
+        let degrees = ticks as f64 * SKY_MINUTES_PER_TICK * SIDEREAL_DEGREES_PER_MINUTE;
+        self.lst = mod360(self.lst + degrees.to_radians());
+        self.day += ticks as f64 * SKY_DAYS_PER_TICK;
+    }
+
+    /// Where something in the sky appears to this observer, as altitude and azimuth.
+    /// See `horizontal` for how those fit into a SkyCoord.
+    pub fn alt_az(&self, coord: SkyCoord) -> SkyCoord {
+        let hour_angle = self.lst - coord.ra;
+        let (sin_dec, cos_dec) = coord.dec.sin_cos();
+        let (sin_lat, cos_lat) = self.latitude.sin_cos();
+
+        let alt = (sin_dec * sin_lat + cos_dec * cos_lat * hour_angle.cos()).clamp(-1.0, 1.0).asin();
+        let az = (-cos_dec * hour_angle.sin()).atan2(sin_dec * cos_lat - cos_dec * sin_lat * hour_angle.cos());
+        horizontal(alt, az)
+    }
+
+    /// The other way around: which ra and dec are at a direction in the observer's frame
+    pub fn ra_dec(&self, horizontal: SkyCoord) -> SkyCoord {
+        let (alt, az) = (horizontal.dec, -horizontal.ra);
+        let (sin_alt, cos_alt) = alt.sin_cos();
+        let (sin_lat, cos_lat) = self.latitude.sin_cos();
+
+        let dec = (sin_alt * sin_lat + cos_alt * cos_lat * az.cos()).clamp(-1.0, 1.0).asin();
+        let hour_angle = (-az.sin() * cos_alt).atan2(sin_alt * cos_lat - cos_alt * sin_lat * az.cos());
+        SkyCoord::radians(mod360(self.lst - hour_angle), dec)
+    }
+
+    pub fn altitude(&self, coord: SkyCoord) -> f64 {
+        self.alt_az(coord).dec
+    }
+
+    /// Whether something's above the horizon
+    pub fn is_up(&self, coord: SkyCoord) -> bool {
+        self.altitude(coord) > 0.0
+    }
+
+    /// Where the Moon is tonight, and how much of it's lit
+    pub fn moon(&self) -> (SkyCoord, Phase) {
+        (orbits::position(Body::Moon, self.day), orbits::phase(Body::Moon, self.day))
+    }
+
+    /// How much the Moon's lighting up the sky: 0 when it's new or down, up to 1 when
+    /// it's full and well up. A half moon is only about a quarter as bright as a full one
+    pub fn moonlight(&self) -> f64 {
+        let (coords, phase) = self.moon();
+        let height = (4.0 * self.altitude(coords).sin()).clamp(0.0, 1.0);
+        phase.illuminated.powi(2) * height
+    }
+
+    /// Sidereal time as hours and minutes, like a clock in an observatory
+    pub fn lst_hours(&self) -> (u32, u32) {
+        let minutes = (self.lst / (2.0 * consts::PI) * 24.0 * 60.0).floor() as u32;
+        (minutes / 60 % 24, minutes % 60)
+    }
+}
+
+/// A direction in the observer's own frame, from its altitude and azimuth (radians,
+/// with azimuth going from north through east). Azimuth goes into the SkyCoord
+/// backwards, so that a SkyView of it shows east on the left when facing south,
+/// just like it does for ra.
+pub fn horizontal(alt: f64, az: f64) -> SkyCoord {
+    SkyCoord::radians(mod360(-az), alt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observer(latitude: f64, lst: f64) -> Observer {
+        Observer::new(latitude.to_radians(), lst.to_radians(), 0.0)
+    }
+
+    fn assert_degrees(radians: f64, degrees: f64) {
+        assert!((radians.to_degrees() - degrees).abs() < 1e-6, "{} isn't {}", radians.to_degrees(), degrees);
+    }
+
+    #[test]
+    fn altitude_and_azimuth() {
+        let observer = observer(35.0, 60.0);
+
+        // Straight overhead, and due south on the horizon
+        assert_degrees(observer.altitude(SkyCoord::degrees(60.0, 35.0)), 90.0);
+        let south = observer.alt_az(SkyCoord::degrees(60.0, -55.0));
+        assert_degrees(south.dec, 0.0);
+        assert_degrees(mod360(-south.ra), 180.0);
+
+        // The pole star stays at the observer's latitude all night
+        for hours in 0..24 {
+            let observer = Observer::new(35f64.to_radians(), (hours as f64 * 15.0).to_radians(), 0.0);
+            assert_degrees(observer.altitude(SkyCoord::degrees(0.0, 90.0)), 35.0);
+        }
+
+        // Something six hours east of the meridian, on the celestial equator, is rising due east
+        let rising = observer.alt_az(SkyCoord::degrees(150.0, 0.0));
+        assert_degrees(rising.dec, 0.0);
+        assert_degrees(mod360(-rising.ra), 90.0);
+    }
+
+    #[test]
+    fn back_to_ra_and_dec() {
+        let observer = observer(35.0, 60.0);
+        for (ra, dec) in [(60.0, 35.0), (101.287, -16.716), (10.685, 41.269), (250.0, 70.0), (150.0, 0.0)].iter() {
+            let coord = SkyCoord::degrees(*ra, *dec);
+            let back = observer.ra_dec(observer.alt_az(coord));
+            assert!(back.separation(coord) < 1e-6, "({}, {}) came back as {:?}", ra, dec, back);
+        }
+    }
+
+    #[test]
+    fn the_sky_turns() {
+        let mut observer = observer(35.0, 60.0);
+        let sirius = SkyCoord::degrees(101.287, -16.716);
+        assert!(observer.is_up(sirius));
+        assert_eq!(observer.lst_hours(), (4, 0));
+
+        // Sirius sets in the evening, and rises again about a day later
+        observer.advance(240);
+        assert!(!observer.is_up(sirius));
+        observer.advance(480);
+        assert!(observer.is_up(sirius));
+        assert_eq!(observer.lst_hours(), (4, 3));
+    }
+}
@@ -1,40 +1,131 @@
 // Structs for different game objects
 use std::fmt;
-use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use anyhow::*;
+use serde::Deserialize;
+use std::f64::consts;
+use crate::points::{SkyCoord, mod360};
+use crate::orbits::{self, Body, Phase};
+use super::platform::Logger;
+use super::economy;
+use super::loader;
+use super::sky::{self, Observer};
+use super::weather::Weather;
+use super::deep_field::Region;
+use super::category;
+use super::events::Event;
+use super::journal::Journal;
+
+
+// Game things are identified (and sorted) by their key alone. Some of their other
+// fields (like coordinates) can't be hashed anyway
+macro_rules! identified_by_key {
+    ($t:ty) => {
+        impl PartialEq for $t {
+            fn eq(&self, other: &Self) -> bool {
+                self.key == other.key
+            }
+        }
+
+        impl Eq for $t {}
+
+        impl Hash for $t {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.key.hash(state);
+            }
+        }
+
+        impl PartialOrd for $t {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $t {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.key.cmp(&other.key)
+            }
+        }
+    }
+}
 
 
+// These are all ordered collections, so that a seeded game always plays out the same way
 #[derive(Debug)]
 pub struct GameState {
     // Which telescopes (or other observing device) the player has obtained
-    pub telescopes: HashSet<Telescope>,
+    pub telescopes: BTreeSet<Telescope>,
+
+    // The key of the telescope the player's observing with. Only one is in use at a time
+    pub active: Option<String>,
+
+    // Which observing sites the player has. They observe from the best one
+    pub sites: BTreeSet<Site>,
+
+    // Eyepieces, filters and cameras. Each one only works with some telescopes
+    pub accessories: BTreeSet<Accessory>,
 
     // The maximum resolving power available to the player
     pub max_power: i32,
 
-    // Which objects can't be observed yet
-    pub unobservables: HashSet<AstroObject>,
+    // Where the player is, and what time it is in their sky
+    pub observer: Observer,
+
+    // What the weather's doing, which takes away some of the telescopes' power
+    pub weather: Weather,
+
+    // Which objects can't be observed right now (or yet)
+    pub unobservables: BTreeSet<AstroObject>,
 
     // Which objects are potentially observable, given the player's resolving power
-    pub observables: HashSet<AstroObject>,
+    // and what's above the horizon. This includes observed objects that the player
+    // could now see more detail on
+    pub observables: BTreeSet<AstroObject>,
 
     // Which objects have been observed, and at what level of detail
-    pub observed: HashMap<AstroObject, usize>
+    pub observed: BTreeMap<AstroObject, usize>,
+
+    // Transient events going on right now, by the key of their object
+    pub events: BTreeMap<String, Event>,
+
+    // How many layers of each region of the deep field have been generated
+    pub explored: BTreeMap<Region, u32>,
+
+    // Every observation the player's made, in order
+    pub journal: Journal,
+
+    // Photons the player has to spend, and how many they've collected all game
+    pub photons: u64,
+    pub photons_earned: u64
 }
 
 impl GameState {
     pub fn init() -> Self {
         GameState {
-            telescopes: HashSet::new(),
+            telescopes: BTreeSet::new(),
+            active: None,
+            sites: BTreeSet::new(),
+            accessories: BTreeSet::new(),
             max_power: 0,
-            unobservables: HashSet::new(),
-            observables: HashSet::new(),
-            observed: HashMap::new()
+            observer: Observer::new(0.0, 0.0, 0.0),
+            weather: Weather::clear(),
+            unobservables: BTreeSet::new(),
+            observables: BTreeSet::new(),
+            observed: BTreeMap::new(),
+            explored: BTreeMap::new(),
+            events: BTreeMap::new(),
+            journal: Journal::new(),
+            photons: 0,
+            photons_earned: 0
         }
     }
 
     pub fn add_data(&mut self, objects: &mut Vec<AstroObject>) {
         while let Some(obj) = objects.pop() {
-            if obj.power_needed <= self.max_power {
+            if self.can_see(&obj) {
                 self.observables.insert(obj);
             }
             else {
@@ -43,134 +134,608 @@ impl GameState {
         }
     }
 
+    /// The telescope the player's observing with
+    pub fn telescope(&self) -> Option<&Telescope> {
+        let key = self.active.as_ref()?;
+        self.telescopes.iter().find(|scope| &scope.key == key)
+    }
+
+    /// Switch to another of the player's telescopes
+    pub fn use_telescope(&mut self, key: &str) -> Result<()> {
+        if !self.telescopes.iter().any(|scope| scope.key == key) {
+            bail!("You don't have a telescope called \"{}\"", key);
+        }
+        self.active = Some(key.to_string());
+        self.refresh_observables();
+        Ok(())
+    }
+
+    // The best accessory of each kind that fits a telescope. Only one of each kind
+    // can be in use at a time
+    fn fitted(&self, scope: &Telescope) -> Vec<&Accessory> {
+        [AccessoryKind::Eyepiece, AccessoryKind::Filter, AccessoryKind::Camera].iter()
+            .filter_map(|kind| {
+                self.accessories.iter()
+                    .filter(|acc| acc.kind == *kind && acc.fits(scope))
+                    .max_by_key(|acc| acc.power_bonus)
+            })
+            .collect()
+    }
+
+    /// What the player can do tonight with a telescope, fitted with whichever of their
+    /// accessories work with it. The weather takes away some power, and the site's
+    /// light pollution limits it. The power comes from the best accessory of each kind,
+    /// but any of them can be swapped in for what it provides (like a filter)
+    pub fn capabilities_of(&self, scope: &Telescope) -> Capabilities {
+        let power_limit = self.site().map_or(i32::MAX, |site| site.power_limit());
+        let bonus: i32 = self.fitted(scope).iter().map(|acc| acc.power_bonus).sum();
+        let power = self.weather.effective_power(scope.max_power + bonus, scope.weather_sensitivity);
+
+        Capabilities {
+            power: std::cmp::min(power, power_limit),
+            provides: self.accessories.iter()
+                .filter(|acc| acc.fits(scope))
+                .flat_map(|acc| acc.provides.iter().cloned())
+                .collect()
+        }
+    }
+
+    /// What the player can do tonight with the telescope they're using
+    pub fn capabilities(&self) -> Option<Capabilities> {
+        self.telescope().map(|scope| self.capabilities_of(scope))
+    }
+
+    /// The resolving power the player actually has: whatever their telescope can do tonight
+    pub fn effective_power(&self) -> i32 {
+        self.capabilities().map_or(0, |caps| caps.power)
+    }
+
+    /// How much sky the player's telescope takes in (radians). An eyepiece narrows it
+    pub fn field_of_view(&self) -> Option<f64> {
+        let scope = self.telescope()?;
+        let eyepiece_fov = self.fitted(scope).iter().filter_map(|acc| acc.fov).next();
+        Some(eyepiece_fov.map_or(scope.fov, |fov| fov.min(scope.fov)))
+    }
+
+    /// The faintest stars the player's telescope can show them tonight
+    pub fn faintest_magnitude(&self) -> f64 {
+        loader::magnitude_for_power(self.effective_power())
+    }
+
+    /// The deepest level of detail the player can reach on an object with their telescope,
+    /// tonight: moonlight makes faint, spread out things harder to see
+    pub fn best_detail<'a>(&self, obj: &'a AstroObject) -> Option<&'a AstroDetail> {
+        obj.best_detail_with(&self.capabilities()?.in_moonlight(obj, self.weather.moon_glare()))
+    }
+
+    /// Where the player's observing from: the best site they have
+    pub fn site(&self) -> Option<&Site> {
+        self.sites.iter().max_by_key(|site| site.tier)
+    }
+
+    /// Whether an object's above the horizon, and bright enough to make out
+    pub fn can_see(&self, obj: &AstroObject) -> bool {
+        self.observer.is_up(obj.coords) && self.best_detail(obj).is_some()
+    }
+
+    // Sort everything that hasn't been observed yet by whether it can be seen right now.
+    // Objects that have been observed go back into the pool while there's more to see
     pub fn refresh_observables(&mut self) {
-        let max_power = self.max_power;
-        let observables: HashSet<AstroObject> = self.unobservables.drain_filter(|obj| {
-            obj.power_needed <= max_power
-        }).collect();
+        let unobserved: Vec<AstroObject> = std::mem::take(&mut self.unobservables).into_iter()
+            .chain(std::mem::take(&mut self.observables))
+            .filter(|obj| !self.observed.contains_key(obj))
+            .collect();
+
+        let capabilities = self.capabilities();
+        let glare = self.weather.moon_glare();
+        let best_level = |obj: &AstroObject| {
+            capabilities.as_ref()
+                .and_then(|caps| obj.best_detail_with(&caps.in_moonlight(obj, glare)))
+                .map(|detail| detail.level)
+        };
+        let visible = |obj: &AstroObject| self.observer.is_up(obj.coords) && best_level(obj).is_some();
+        let (observables, unobservables): (BTreeSet<AstroObject>, BTreeSet<AstroObject>) =
+            unobserved.into_iter().partition(|obj| visible(obj));
+
+        let observed_again: Vec<AstroObject> = self.observed.iter()
+            .filter(|(obj, detail_level)| {
+                !self.is_gone(obj) && visible(obj) && best_level(obj).is_some_and(|level| level > **detail_level)
+            })
+            .map(|(obj, _)| obj.clone())
+            .collect();
+
+        self.unobservables = unobservables;
+        self.observables = observables;
+        self.observables.extend(observed_again);
+    }
+
+    /// Move everything that moves to where it is at a generation
+    // Objects are sorted by key, so moving one doesn't change where it goes in its set;
+    // only the few that move get swapped for their new selves
+    pub fn move_objects(&mut self, generation: i32) {
+        let moving: Vec<AstroObject> = self.observables.iter()
+            .chain(self.unobservables.iter())
+            .chain(self.observed.keys())
+            .filter(|obj| obj.motion.is_some() && obj.category.behavior().moves())
+            .cloned()
+            .collect();
+        for mut obj in moving {
+            if let Some(motion) = obj.motion {
+                obj.coords = motion.position_at(generation);
+            }
+            self.update_object(obj);
+        }
+    }
+
+    /// Swap in a new version of an object (say, one that's got brighter), wherever it is
+    pub fn update_object(&mut self, obj: AstroObject) {
+        if let Some(detail_level) = self.observed.remove(&obj) {
+            self.observed.insert(obj, detail_level);
+        }
+        else if self.observables.remove(&obj) {
+            self.observables.insert(obj);
+        }
+        else if self.unobservables.remove(&obj) {
+            self.unobservables.insert(obj);
+        }
+    }
+
+    /// Take an event's object out of the sky for good. If the player observed it,
+    /// it stays in the observed map, so their progress doesn't go backwards
+    pub fn expire(&mut self, key: &str) {
+        self.events.remove(key);
+        self.observables.retain(|obj| obj.key != key);
+        self.unobservables.retain(|obj| obj.key != key);
+    }
+
+    /// Whether something's been and gone, like a supernova that's faded
+    pub fn is_gone(&self, obj: &AstroObject) -> bool {
+        obj.category.behavior().is_transient() && !self.events.contains_key(&obj.key)
+    }
+
+    /// Look an object up by its key, wherever it is
+    pub fn find(&self, key: &str) -> Option<&AstroObject> {
+        self.observables.iter()
+            .chain(self.unobservables.iter())
+            .chain(self.observed.keys())
+            .find(|obj| obj.key == key)
+    }
+
+    /// Take an object that hasn't been observed yet out of the game, by its key
+    pub fn take_unobserved(&mut self, key: &str) -> Option<AstroObject> {
+        let obj = self.observables.iter()
+            .chain(self.unobservables.iter())
+            .find(|obj| obj.key == key)
+            .cloned()?;
+        self.observables.remove(&obj);
+        self.unobservables.remove(&obj);
+        Some(obj)
+    }
+
+    /// The number of detail levels reached, summed over every observed object
+    pub fn detail_levels_reached(&self) -> usize {
+        self.observed.values().map(|detail_level| detail_level + 1).sum()
+    }
+
+    /// The lowest-tier telescope the player hasn't obtained yet
+    pub fn next_telescope(&self, telescopes: &TelescopeIndex) -> Option<Telescope> {
+        telescopes.values()
+            .filter(|scope| !self.telescopes.contains(scope))
+            .min_by_key(|scope| scope.tier)
+            .cloned()
+    }
 
-        self.observables.extend(observables);
+    /// The lowest-tier site the player hasn't obtained yet
+    pub fn next_site(&self, sites: &SiteIndex) -> Option<Site> {
+        sites.values()
+            .filter(|site| !self.sites.contains(site))
+            .min_by_key(|site| site.tier)
+            .cloned()
     }
 
+    /// Collect some ticks' worth of photons. Returns how many
+    pub fn collect_photons(&mut self, ticks: u64) -> u64 {
+        let income = economy::income(self).saturating_mul(ticks);
+        self.photons = self.photons.saturating_add(income);
+        self.photons_earned = self.photons_earned.saturating_add(income);
+        income
+    }
+
+    pub fn buy_telescope(&mut self, scope: Telescope) -> Result<()> {
+        if self.telescopes.contains(&scope) {
+            bail!("You already have {}", scope.name);
+        }
+        if !scope.is_unlocked(self) {
+            bail!("{} isn't available yet", scope.name);
+        }
+        self.photons = self.photons.checked_sub(scope.price)
+            .with_context(|| format!("{} costs {} photons, but you only have {}", scope.name, scope.price, self.photons))?;
+        self.add_telescope(scope);
+        Ok(())
+    }
+
+    // A new telescope gets used straight away, if it's more powerful than the current one
     pub fn add_telescope(&mut self, scope: Telescope) {
         self.max_power = std::cmp::max(self.max_power, scope.max_power);
+        if self.telescope().is_none_or(|active| scope.max_power > active.max_power) {
+            self.active = Some(scope.key.clone());
+        }
         self.telescopes.insert(scope);
         self.refresh_observables();
     }
 
-    pub fn log(&self) {
-        log!("\n====== OBSERVATION REPORT ======");
-        log!("You have these observing devices:");
+    pub fn buy_site(&mut self, site: Site) -> Result<()> {
+        if self.sites.contains(&site) {
+            bail!("You already have {}", site.name);
+        }
+        if !site.is_unlocked(self) {
+            bail!("{} isn't available yet", site.name);
+        }
+        self.photons = self.photons.checked_sub(site.price)
+            .with_context(|| format!("{} costs {} photons, but you only have {}", site.name, site.price, self.photons))?;
+        self.add_site(site);
+        Ok(())
+    }
+
+    pub fn add_site(&mut self, site: Site) {
+        self.sites.insert(site);
+        self.refresh_observables();
+    }
+
+    pub fn buy_accessory(&mut self, acc: Accessory) -> Result<()> {
+        if self.accessories.contains(&acc) {
+            bail!("You already have {}", acc.name);
+        }
+        if !acc.is_unlocked(self) {
+            bail!("{} isn't available yet", acc.name);
+        }
+        if !self.telescopes.iter().any(|scope| acc.fits(scope)) {
+            bail!("{} doesn't fit any of your telescopes", acc.name);
+        }
+        self.photons = self.photons.checked_sub(acc.price)
+            .with_context(|| format!("{} costs {} photons, but you only have {}", acc.name, acc.price, self.photons))?;
+        self.add_accessory(acc);
+        Ok(())
+    }
+
+    pub fn add_accessory(&mut self, acc: Accessory) {
+        self.accessories.insert(acc);
+        self.refresh_observables();
+    }
 
-        if self.telescopes.len() == 0 {
-            log!("  nothing")
+    pub fn log(&self, logger: &dyn Logger) {
+        log_to!(logger, "\n====== OBSERVATION REPORT ======");
+        log_to!(logger, "You have {} photons (collecting {} per tick)", self.photons, economy::income(self));
+        log_to!(logger, "You have these observing devices:");
+
+        if self.telescopes.is_empty() {
+            log_to!(logger, "  nothing")
         }
 
         self.telescopes.iter().for_each(|t| {
-            log!("  {} (resolving power: {})", t.name, t.max_power);
+            let in_use = if self.active.as_ref() == Some(&t.key) { ", in use" } else { "" };
+            log_to!(logger, "  {} (resolving power: {}{})", t.name, t.max_power, in_use);
+        });
+        self.accessories.iter().for_each(|acc| {
+            log_to!(logger, "  {} ({:?})", acc.name, acc.kind);
         });
 
-        log!("You have observed these astronomical objects:");
+        if let Some(site) = self.site() {
+            log_to!(logger, "You are observing from {} (light pollution: Bortle {})", site.name, site.bortle);
+        }
+
+        log_to!(logger, "You have observed these astronomical objects:");
         self.observed.iter().for_each(|(o, detail_level)| {
-            log!("  {} (at detail level {})", o.name, detail_level);
+            log_to!(logger, "  {} (at detail level {})", o.name, detail_level);
         });
 
-        if self.observed.len() == 0 {
-            log!("  nothing")
+        if self.observed.is_empty() {
+            log_to!(logger, "  nothing")
         }
 
-        log!("With resolving power {}, You could also observe:", self.max_power);
+        log_to!(logger, "Tonight's weather: {}", self.weather);
+        log_to!(logger, "With resolving power {} (out of {}), You could also observe:", self.effective_power(), self.max_power);
         self.observables.iter().for_each(|o| {
-            log!("  {} (needs power of {})", o, o.power_needed);
+            log_to!(logger, "  {} (needs power of {})", o, o.power_needed);
         });
 
-        if self.observables.len() == 0 {
-            log!("  nothing")
+        if self.observables.is_empty() {
+            log_to!(logger, "  nothing")
         }
 
-        log!("================================");
+        log_to!(logger, "================================");
     }
 }
 
 
+// What each of these is like lives in the category module
 #[derive(Hash, Eq, PartialEq, Debug, Clone)]
 pub enum AstroObjectCategory {
     Star,
+    Planet,
+    Moon,
+    Comet,
+    MeteorShower,
+    Supernova,
+    Exoplanet,
+    Nebula,
+    SupernovaRemnant,
+    OpenCluster,
+    GlobularCluster,
     Galaxy,
+    GalaxyCluster,
+    Quasar,
     AlienShip,
 }
 
-#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+impl FromStr for AstroObjectCategory {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        category::ALL.iter()
+            .find(|category| category.behavior().key() == s)
+            .cloned()
+            .with_context(|| {
+                let keys: Vec<&str> = category::ALL.iter().map(|category| category.behavior().key()).collect();
+                format!("unknown category \"{}\" (expected one of {})", s, keys.join(", "))
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct AstroObject {
     pub key: String,
     pub name: String,
     pub category: AstroObjectCategory,
-    pub power_needed: i32, // may be redundant since AstroDetail has it
+    pub power_needed: i32, // to see anything at all; the same as the first AstroDetail's
+    pub coords: SkyCoord, // where it is in the sky
+    pub magnitude: Option<f64>, // apparent magnitude, if it has a meaningful one
+    pub distance_ly: Option<f64>, // in light years, if anyone knows
+    pub motion: Option<Motion>, // how it wanders across the sky, if it does
     pub detail: Vec<AstroDetail>
 }
 
+identified_by_key!(AstroObject);
+
+impl AstroObject {
+    /// The deepest level of detail the given power can reach. Levels are reached in order,
+    /// so one that needs more power stops the progression even if later ones need less.
+    /// None if the first level is already out of reach.
+    pub fn best_detail_for(&self, power: i32) -> Option<&AstroDetail> {
+        self.best_detail_with(&Capabilities::power(power))
+    }
+
+    /// Same as best_detail_for, but levels can also need accessories (like a filter)
+    pub fn best_detail_with(&self, capabilities: &Capabilities) -> Option<&AstroDetail> {
+        self.detail.iter()
+            .take_while(|detail| capabilities.can_reach(detail))
+            .last()
+    }
+
+    /// The level of detail after the given one, if there is one
+    pub fn next_detail(&self, detail_level: usize) -> Option<&AstroDetail> {
+        self.remaining_levels(detail_level).first()
+    }
+
+    /// Every level of detail after the given one
+    pub fn remaining_levels(&self, detail_level: usize) -> &[AstroDetail] {
+        self.detail.get(detail_level + 1..).unwrap_or(&[])
+    }
+}
+
+/// How something moves against the stars
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Motion {
+    // A steady drift from where it was at some generation (radians per tick)
+    Drift { from: SkyCoord, since: i32, ra_per_tick: f64, dec_per_tick: f64 },
+    // Following an orbit, from a day number at generation 0
+    Orbit { body: Body, epoch: f64 },
+}
+
+impl Motion {
+    pub fn position_at(&self, generation: i32) -> SkyCoord {
+        match *self {
+            Motion::Drift { from, since, ra_per_tick, dec_per_tick } => {
+                let ticks = (generation - since) as f64;
+                SkyCoord::radians(
+                    mod360(from.ra + ra_per_tick * ticks),
+                    (from.dec + dec_per_tick * ticks).clamp(-consts::FRAC_PI_2, consts::FRAC_PI_2)
+                )
+            }
+            Motion::Orbit { body, epoch } => orbits::position(body, day(epoch, generation)),
+        }
+    }
+
+    /// How lit up it is, for the things that have phases we can see
+    pub fn phase_at(&self, generation: i32) -> Option<Phase> {
+        match *self {
+            Motion::Orbit { body, epoch } if body.has_phases() => Some(orbits::phase(body, day(epoch, generation))),
+            _ => None
+        }
+    }
+}
+
+fn day(epoch: f64, generation: i32) -> f64 {
+    epoch + generation as f64 * sky::SKY_DAYS_PER_TICK
+}
+
 #[derive(Hash, Eq, PartialEq, Debug, Clone)]
 pub struct AstroDetail {
     pub level: usize,
     pub power_needed: i32,
+    pub requires: Vec<String>, // things an accessory has to provide, like "h_alpha"
     pub discovery_text: String
 }
 
 impl fmt::Display for AstroObject {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "\"{}\", a {:?} with {} levels of observable detail", self.name, self.category, self.detail.len())
-    }
-}
-
-#[macro_export]
-macro_rules! astro {
-    ( $cat:tt: $key:tt, $name:tt, [$( $power:expr => $desc:tt )*] ) => {
-        {
-            let mut obj = AstroObject {
-                key: String::from($key),
-                name: String::from($name),
-                category: AstroObjectCategory::$cat,
-                power_needed: 9999,
-                detail: vec![]
-            };
-            $(
-                obj.detail.push(AstroDetail {
-                    level: obj.detail.len(),
-                    power_needed: $power,
-                    discovery_text: String::from($desc)
-                });
-
-                if $power < obj.power_needed {
-                    obj.power_needed = $power
-                }
-            )*
-
-            obj
-        }
+        write!(f, "\"{}\", a {} with {} levels of observable detail", self.name, self.category.behavior().name(), self.detail.len())
     }
 }
 
 /// Telescopes
 
-#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct Telescope {
     pub key: String,
     pub name: String,
     pub description: String,
     pub max_power: i32,
+    pub tier: usize, // position in the progression; set when the index is built
+    pub unlock: Vec<Unlock>, // all of these must be met before the player gets it
+    pub price: u64, // in photons. Free telescopes are acquired as soon as they're unlocked
+    pub weather_sensitivity: f64, // how much the weather affects it: 1 for a backyard scope
+    pub fov: f64, // how much sky it takes in (radians)
+    pub slew_speed: f64, // how fast it can be turned to point somewhere else (radians per tick)
+    pub image: String // the cursor image, relative to www/
 }
 
-macro_rules! telescope {
-    ( $key:tt => $name:tt, $power:tt, $desc:tt ) => {
-        Telescope {
-            key: String::from($key),
-            name: String::from($name),
-            max_power: $power,
-            description: String::from($desc)
+identified_by_key!(Telescope);
+
+impl Telescope {
+    pub fn is_unlocked(&self, state: &GameState) -> bool {
+        self.unlock.iter().all(|condition| condition.is_met(state))
+    }
+}
+
+/// Accessories
+
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+pub enum AccessoryKind {
+    Eyepiece,
+    Filter,
+    Camera,
+}
+
+impl FromStr for AccessoryKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "eyepiece" => Ok(AccessoryKind::Eyepiece),
+            "filter" => Ok(AccessoryKind::Filter),
+            "camera" => Ok(AccessoryKind::Camera),
+            _ => Err(anyhow!("unknown kind \"{}\" (expected eyepiece, filter or camera)", s))
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct Accessory {
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub kind: AccessoryKind,
+    pub power_bonus: i32, // extra resolving power, from magnification or long exposures
+    pub fov: Option<f64>, // for eyepieces: how much sky they take in (radians)
+    pub provides: Vec<String>, // what it lets details require, like "h_alpha" for a filter
+    pub fits: Vec<String>, // keys of the telescopes it works with
+    pub tier: usize, // just for ordering; accessories aren't a single progression
+    pub unlock: Vec<Unlock>,
+    pub price: u64
+}
+
+identified_by_key!(Accessory);
+
+impl Accessory {
+    pub fn is_unlocked(&self, state: &GameState) -> bool {
+        self.unlock.iter().all(|condition| condition.is_met(state))
+    }
+
+    pub fn fits(&self, scope: &Telescope) -> bool {
+        self.fits.contains(&scope.key)
+    }
+}
+
+/// What the player can observe with a telescope and the accessories fitted to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    pub power: i32,
+    pub provides: BTreeSet<String>
+}
+
+impl Capabilities {
+    /// Just resolving power, with no accessories
+    pub fn power(power: i32) -> Self {
+        Self { power, provides: BTreeSet::new() }
+    }
+
+    pub fn can_reach(&self, detail: &AstroDetail) -> bool {
+        detail.power_needed <= self.power && detail.requires.iter().all(|thing| self.provides.contains(thing))
+    }
+
+    /// What's left for an object once the moon's glare is taken away, if it's the kind of thing that washes out
+    pub fn in_moonlight(&self, obj: &AstroObject, glare: i32) -> Self {
+        let glare = if obj.category.behavior().is_diffuse() { glare } else { 0 };
+        Self { power: self.power - glare, provides: self.provides.clone() }
+    }
+}
+
+
+/// Observing sites
+
+#[derive(Debug, Clone)]
+pub struct Site {
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub bortle: u8, // light pollution, on the Bortle scale: 1 is pristine, 9 is a city center
+    pub sky_color: String, // what the night sky looks like from here
+    pub tier: usize, // position in the progression, like telescopes
+    pub unlock: Vec<Unlock>,
+    pub price: u64
+}
+
+identified_by_key!(Site);
+
+// The darkest skies (Bortle 1) allow this much resolving power, and every step
+// up the Bortle scale takes some away
+const POWER_LIMIT_DARKEST: i32 = 22;
+const POWER_LIMIT_PER_BORTLE: i32 = 2;
+
+impl Site {
+    pub fn is_unlocked(&self, state: &GameState) -> bool {
+        self.unlock.iter().all(|condition| condition.is_met(state))
+    }
+
+    /// The most resolving power that's any use under this sky, however big the telescope
+    pub fn power_limit(&self) -> i32 {
+        POWER_LIMIT_DARKEST - POWER_LIMIT_PER_BORTLE * (self.bortle as i32 - 1)
+    }
+
+    /// How much of the starry background shows through the light pollution, from 0 to 1
+    pub fn stars_visible(&self) -> f64 {
+        (9 - self.bortle) as f64 / 8.0
+    }
+}
+
+
+/// Something the player has to achieve before getting a telescope (or a site).
+/// In the game data, these look like `{ "objects_observed": 2 }`
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unlock {
+    // Number of distinct objects observed
+    ObjectsObserved(usize),
+
+    // Detail levels reached, summed over all observed objects
+    DetailLevels(usize),
+
+    // Photons collected over the whole game (whether or not they've been spent)
+    PhotonsEarned(u64),
+}
+
+impl Unlock {
+    pub fn is_met(&self, state: &GameState) -> bool {
+        match *self {
+            Unlock::ObjectsObserved(n) => state.observed.len() >= n,
+            Unlock::DetailLevels(n) => state.detail_levels_reached() >= n,
+            Unlock::PhotonsEarned(n) => state.photons_earned >= n,
+        }
+    }
+}
+
+
 pub type TelescopeIndex = HashMap<String, Telescope>;
 
 pub trait Keyed {
@@ -178,9 +743,13 @@ pub trait Keyed {
     fn get_by_key(&self, key: &str) -> Option<Self::KeyedItem>;
 }
 
-impl Keyed for TelescopeIndex {
-    type KeyedItem = Telescope;
-    fn get_by_key(&self, key: &str) -> Option<Telescope> {
+pub type SiteIndex = HashMap<String, Site>;
+
+pub type AccessoryIndex = HashMap<String, Accessory>;
+
+impl<T: Clone> Keyed for HashMap<String, T> {
+    type KeyedItem = T;
+    fn get_by_key(&self, key: &str) -> Option<T> {
         self.get(key).cloned()
     }
 }
@@ -191,4 +760,232 @@ impl Keyed for TelescopeIndex {
 //     pub width: u32,
 //     pub height: u32,
 //     pub canvas_id: String,
-// }
\ No newline at end of file
+// }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use rand::rngs::SmallRng;
+
+    fn object_with_powers(powers: &[i32]) -> AstroObject {
+        AstroObject {
+            key: String::from("test"),
+            name: String::from("Test"),
+            category: AstroObjectCategory::Star,
+            power_needed: powers.first().copied().unwrap_or(9999),
+            coords: SkyCoord::degrees(0.0, 0.0),
+            magnitude: None,
+            distance_ly: None,
+            motion: None,
+            detail: powers.iter().enumerate().map(|(level, &power_needed)| AstroDetail {
+                level,
+                power_needed,
+                requires: vec![],
+                discovery_text: format!("level {}", level)
+            }).collect()
+        }
+    }
+
+    // A state that's observed some objects (at least one, if there are detail levels),
+    // with detail levels and photons to match
+    fn state_with(objects: usize, detail_levels: usize, photons_earned: u64) -> GameState {
+        let objects = if detail_levels > 0 { objects.max(1) } else { objects };
+        assert!(detail_levels >= objects);
+        let mut state = GameState::init();
+        for i in 0..objects {
+            let mut obj = object_with_powers(&[1; 20]);
+            obj.key = format!("object_{}", i);
+            let level = if i == 0 { detail_levels - objects } else { 0 };
+            state.observed.insert(obj, level);
+        }
+        state.photons_earned = photons_earned;
+        assert_eq!(state.detail_levels_reached(), detail_levels);
+        state
+    }
+
+    fn telescope(key: &str, unlock: Vec<Unlock>) -> Telescope {
+        Telescope {
+            key: key.to_string(),
+            name: key.to_string(),
+            description: String::new(),
+            max_power: 1,
+            tier: 0,
+            unlock,
+            price: 0,
+            weather_sensitivity: 1.0,
+            fov: 1.0,
+            slew_speed: 1.0,
+            image: String::new()
+        }
+    }
+
+    fn unlocked<'a>(telescopes: &'a [Telescope], state: &GameState) -> Vec<&'a str> {
+        telescopes.iter().filter(|scope| scope.is_unlocked(state)).map(|scope| scope.key.as_str()).collect()
+    }
+
+    #[test]
+    fn unlock_at_each_threshold() {
+        let telescopes = vec![
+            telescope("free", vec![]),
+            telescope("objects", vec![Unlock::ObjectsObserved(2)]),
+            telescope("detail", vec![Unlock::DetailLevels(4)]),
+            telescope("photons", vec![Unlock::PhotonsEarned(100)]),
+            telescope("both", vec![Unlock::ObjectsObserved(3), Unlock::DetailLevels(6)])
+        ];
+        assert_eq!(unlocked(&telescopes, &state_with(0, 0, 0)), vec!["free"]);
+
+        assert_eq!(unlocked(&telescopes, &state_with(1, 1, 0)), vec!["free"]);
+        assert_eq!(unlocked(&telescopes, &state_with(2, 2, 0)), vec!["free", "objects"]);
+
+        assert_eq!(unlocked(&telescopes, &state_with(1, 3, 0)), vec!["free"]);
+        assert_eq!(unlocked(&telescopes, &state_with(1, 4, 0)), vec!["free", "detail"]);
+
+        assert_eq!(unlocked(&telescopes, &state_with(0, 0, 99)), vec!["free"]);
+        assert_eq!(unlocked(&telescopes, &state_with(0, 0, 100)), vec!["free", "photons"]);
+
+        // Every condition has to be met
+        assert_eq!(unlocked(&telescopes, &state_with(2, 6, 0)), vec!["free", "objects", "detail"]);
+        assert_eq!(unlocked(&telescopes, &state_with(3, 5, 0)), vec!["free", "objects", "detail"]);
+        assert_eq!(unlocked(&telescopes, &state_with(3, 6, 0)), vec!["free", "objects", "detail", "both"]);
+    }
+
+    // In the real progression, crossing the last threshold for a telescope unlocks
+    // that telescope and nothing else
+    #[test]
+    fn unlock_the_game_datas_telescopes() {
+        let mut telescopes: Vec<Telescope> = super::super::data::game_data().telescopes.into_values().collect();
+        telescopes.sort_by_key(|scope| scope.tier);
+
+        for scope in telescopes.iter().filter(|scope| !scope.unlock.is_empty()) {
+            let (mut objects, mut detail_levels, mut photons) = (0, 0, 0);
+            for condition in scope.unlock.iter() {
+                match *condition {
+                    Unlock::ObjectsObserved(n) => objects = n,
+                    Unlock::DetailLevels(n) => detail_levels = n,
+                    Unlock::PhotonsEarned(n) => photons = n
+                }
+            }
+            let detail_levels = detail_levels.max(objects);
+            let at = unlocked(&telescopes, &state_with(objects, detail_levels, photons));
+
+            let mut just_below = vec![];
+            if objects > 0 {
+                just_below.push(state_with(objects - 1, detail_levels, photons));
+            }
+            if scope.unlock.iter().any(|condition| matches!(condition, Unlock::DetailLevels(_))) {
+                just_below.push(state_with(objects.min(detail_levels - 1), detail_levels - 1, photons));
+            }
+            if photons > 0 {
+                just_below.push(state_with(objects, detail_levels, photons - 1));
+            }
+            assert!(!just_below.is_empty());
+            for state in just_below {
+                let below = unlocked(&telescopes, &state);
+                let newly: Vec<&str> = at.iter().filter(|key| !below.contains(key)).copied().collect();
+                assert_eq!(newly, vec![scope.key.as_str()]);
+            }
+        }
+    }
+
+    fn filter(key: &str, provides: &str, fits: &str) -> Accessory {
+        Accessory {
+            key: key.to_string(),
+            name: key.to_string(),
+            description: String::new(),
+            kind: AccessoryKind::Filter,
+            power_bonus: 0,
+            fov: None,
+            provides: vec![provides.to_string()],
+            fits: vec![fits.to_string()],
+            tier: 0,
+            unlock: vec![],
+            price: 0
+        }
+    }
+
+    #[test]
+    fn every_filter_can_be_used() {
+        let scope = telescope("reflector", vec![]);
+        let mut state = GameState::init();
+        state.add_telescope(scope.clone());
+        state.add_accessory(filter("h_alpha_filter", "h_alpha", "reflector"));
+        state.add_accessory(filter("oiii_filter", "oiii", "reflector"));
+        state.add_accessory(filter("sii_filter", "sii", "somebody_elses_scope"));
+
+        let provides: Vec<String> = state.capabilities_of(&scope).provides.into_iter().collect();
+        assert_eq!(provides, vec!["h_alpha".to_string(), "oiii".to_string()]);
+
+        // A detail that needs either one is in reach
+        let mut obj = object_with_powers(&[1, 1, 1]);
+        obj.detail[1].requires = vec!["h_alpha".to_string()];
+        obj.detail[2].requires = vec!["oiii".to_string()];
+        assert_eq!(state.best_detail(&obj).map(|detail| detail.level), Some(2));
+    }
+
+    #[test]
+    fn things_that_move() {
+        let mut planet = object_with_powers(&[1, 2]);
+        planet.key = String::from("planet");
+        planet.category = AstroObjectCategory::Planet;
+        planet.motion = Some(Motion::Drift {
+            from: SkyCoord::degrees(359.0, 10.0),
+            since: 0,
+            ra_per_tick: 0.5f64.to_radians(),
+            dec_per_tick: -0.1f64.to_radians()
+        });
+        let mut state = GameState::init();
+        state.add_data(&mut vec![planet.clone(), object_with_powers(&[1])]);
+
+        state.move_objects(10);
+        let moved = state.unobservables.iter().find(|obj| **obj == planet).unwrap();
+        assert!(moved.coords.separation(SkyCoord::degrees(4.0, 9.0)) < 1e-9);
+        assert!(moved.category.behavior().moves());
+
+        // Its position only depends on the generation
+        state.move_objects(0);
+        let moved = state.unobservables.iter().find(|obj| **obj == planet).unwrap();
+        assert!(moved.coords.separation(SkyCoord::degrees(359.0, 10.0)) < 1e-9);
+    }
+
+    #[test]
+    fn best_detail_doesnt_skip_levels() {
+        let obj = object_with_powers(&[3, 12, 6]);
+        assert_eq!(obj.best_detail_for(2), None);
+        assert_eq!(obj.best_detail_for(3).map(|d| d.level), Some(0));
+        assert_eq!(obj.best_detail_for(11).map(|d| d.level), Some(0));
+        assert_eq!(obj.best_detail_for(12).map(|d| d.level), Some(2));
+        assert_eq!(object_with_powers(&[]).best_detail_for(i32::MAX), None);
+    }
+
+    // Random detail lists (including empty and out-of-order ones), checked at every power
+    #[test]
+    fn detail_progression_properties() {
+        let mut rng = SmallRng::seed_from_u64(11);
+        for _ in 0..2000 {
+            let len = rng.gen_range(0..6);
+            let powers: Vec<i32> = (0..len).map(|_| rng.gen_range(-5..30)).collect();
+            let obj = object_with_powers(&powers);
+
+            for power in -10..40 {
+                match obj.best_detail_for(power) {
+                    None => assert!(powers.first().is_none_or(|&first| first > power), "{:?} at {}", powers, power),
+                    Some(best) => {
+                        // It's a real level, every level up to it is within reach, and the next one isn't
+                        assert_eq!(obj.detail[best.level], *best);
+                        assert!(powers[..=best.level].iter().all(|&p| p <= power), "{:?} at {}", powers, power);
+                        assert!(obj.next_detail(best.level).is_none_or(|next| next.power_needed > power));
+                    }
+                }
+            }
+
+            for level in 0..len + 2 {
+                let remaining = obj.remaining_levels(level);
+                assert_eq!(remaining.len(), len.saturating_sub(level + 1));
+                assert_eq!(obj.next_detail(level), remaining.first());
+                assert!(remaining.iter().all(|detail| detail.level > level));
+            }
+        }
+    }
+}
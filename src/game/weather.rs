@@ -0,0 +1,186 @@
+// Clouds, seeing, humidity and moonlight, which all take away some resolving power.
+// Each drifts smoothly, worked out from the seed and the generation.
+
+use std::fmt;
+use std::f64::consts;
+use rand::prelude::*;
+use rand::rngs::SmallRng;
+use serde::{Serialize, Deserialize};
+use super::seed::{self, Seed};
+use super::sky::Observer;
+
+// Roughly how many ticks it takes each kind of weather to change completely
+const CLOUD_TICKS: i32 = 40;
+const SEEING_TICKS: i32 = 7;
+const HUMIDITY_TICKS: i32 = 150;
+
+// How much of a telescope's power each one takes away, at its worst
+const CLOUD_LOSS: f64 = 0.5;
+const SEEING_LOSS: f64 = 0.2;
+const HUMIDITY_LOSS: f64 = 0.1;
+
+// Moonlight doesn't blur anything, but it washes out faint, spread out things like
+// galaxies. A full moon high in the sky means they need this much more power
+const MOON_GLARE: f64 = 4.0;
+
+// Everything's from 0 (perfect) to 1 (awful)
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Weather {
+    pub clouds: f64,
+    pub seeing: f64,
+    pub humidity: f64,
+    pub moonlight: f64
+}
+
+impl Weather {
+    /// A perfect night
+    pub fn clear() -> Self {
+        Self { clouds: 0.0, seeing: 0.0, humidity: 0.0, moonlight: 0.0 }
+    }
+
+    /// The weather in a game, at a generation, for an observer at that generation
+    pub fn at(seed: Seed, generation: i32, observer: &Observer) -> Self {
+        let drift = |stream: &str, ticks: i32| drift(seed.derive(stream), generation, ticks);
+        Self {
+            // Clear nights are more common than cloudy ones
+            clouds: drift(seed::CLOUDS, CLOUD_TICKS).powi(2),
+            seeing: drift(seed::SEEING, SEEING_TICKS),
+            humidity: drift(seed::HUMIDITY, HUMIDITY_TICKS),
+            moonlight: observer.moonlight()
+        }
+    }
+
+    /// The fraction of a telescope's power that's lost, from 0 to 1. Sensitivity is how
+    /// much the telescope suffers from the weather: 1 for a backyard scope, and less
+    /// for a big observatory up on a mountain.
+    pub fn loss(&self, sensitivity: f64) -> f64 {
+        let loss = CLOUD_LOSS * self.clouds
+            + SEEING_LOSS * self.seeing
+            + HUMIDITY_LOSS * self.humidity;
+        (loss * sensitivity).clamp(0.0, 1.0)
+    }
+
+    /// How much more power the moonlight makes faint, spread out things need tonight
+    pub fn moon_glare(&self) -> i32 {
+        (MOON_GLARE * self.moonlight).round() as i32
+    }
+
+    /// How much of a telescope's resolving power is left tonight, given how sensitive it is
+    /// to the weather. Even on the worst night, it can pick out the very brightest things.
+    pub fn effective_power(&self, power: i32, sensitivity: f64) -> i32 {
+        if power <= 0 {
+            return power;
+        }
+        let power = power as f64 * (1.0 - self.loss(sensitivity));
+        std::cmp::max(1, power.floor() as i32)
+    }
+}
+
+impl fmt::Display for Weather {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |x: f64| (x * 100.0).round();
+        write!(
+            f, "clouds {}%, seeing {}%, humidity {}%, moonlight {}%",
+            percent(self.clouds), percent(self.seeing), percent(self.humidity), percent(self.moonlight)
+        )
+    }
+}
+
+// Smooth noise from 0 to 1: random values every so many ticks, eased in between
+fn drift(stream: u64, generation: i32, ticks: i32) -> f64 {
+    let t = generation as f64 / ticks as f64;
+    let i = t.floor();
+    let ease = (1.0 - ((t - i) * consts::PI).cos()) / 2.0;
+    let (from, to) = (keyframe(stream, i as i64), keyframe(stream, i as i64 + 1));
+    from + (to - from) * ease
+}
+
+fn keyframe(stream: u64, i: i64) -> f64 {
+    SmallRng::seed_from_u64(stream ^ (i as u64).wrapping_mul(0x9e3779b97f4a7c15)).gen()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::data;
+    use crate::orbits;
+
+    // What a telescope can do in some weather
+    fn power(weather: &Weather, key: &str) -> i32 {
+        let scope = data::game_data().telescopes.remove(key).unwrap();
+        weather.effective_power(scope.max_power, scope.weather_sensitivity)
+    }
+
+    // The weather over a game, from the start of the game data's night
+    fn weather_from(seed: Seed) -> impl Fn(i32) -> Weather {
+        let start = data::game_data().observer;
+        move |generation| {
+            let mut observer = start;
+            observer.advance(generation);
+            Weather::at(seed, generation, &observer)
+        }
+    }
+
+    #[test]
+    fn weather_is_seeded() {
+        let (one, again, two) = (weather_from(Seed(1)), weather_from(Seed(1)), weather_from(Seed(2)));
+        for generation in (0..5000).step_by(37) {
+            assert_eq!(one(generation), again(generation));
+        }
+        assert_ne!(one(100), two(100));
+    }
+
+    #[test]
+    fn weather_drifts_smoothly() {
+        let weather_at = weather_from(Seed(5));
+        let mut previous = weather_at(0);
+        let mut cloudiest: f64 = 0.0;
+        for generation in 1..5000 {
+            let weather = weather_at(generation);
+            for x in [weather.clouds, weather.seeing, weather.humidity, weather.moonlight].iter() {
+                assert!((0.0..=1.0).contains(x), "{} at {}", weather, generation);
+            }
+            assert!((weather.clouds - previous.clouds).abs() < 0.1);
+            assert!((weather.humidity - previous.humidity).abs() < 0.05);
+            assert!((weather.moonlight - previous.moonlight).abs() < 0.05);
+            cloudiest = cloudiest.max(weather.clouds);
+            previous = weather;
+        }
+        // It's not always clear
+        assert!(cloudiest > 0.5);
+    }
+
+    #[test]
+    fn moonlight_follows_the_moon() {
+        let moonlight = |date: &str, hours_from_moon: f64| {
+            let mut observer = Observer::new(35f64.to_radians(), 0.0, orbits::day_number(date).unwrap());
+            observer.lst = observer.moon().0.ra + (hours_from_moon * 15.0).to_radians();
+            Weather::at(Seed(1), 0, &observer)
+        };
+
+        // A full moon high in the sky drowns out faint things, but not once it's set
+        let full = moonlight("2000-01-21", 0.0);
+        assert!(full.moonlight > 0.9);
+        assert_eq!(full.moon_glare(), 4);
+        assert_eq!(moonlight("2000-01-21", 12.0).moonlight, 0.0);
+
+        // A new moon doesn't light up anything, and a quarter moon not much
+        assert!(moonlight("2000-01-06", 0.0).moonlight < 0.01);
+        assert!(moonlight("2000-01-14", 0.0).moonlight < 0.3);
+        assert_eq!(moonlight("2000-01-06", 0.0).moon_glare(), 0);
+    }
+
+    #[test]
+    fn big_telescopes_cope_better() {
+        let clear = Weather::clear();
+        assert_eq!(power(&clear, "dobsonian_20in"), 18);
+        assert_eq!(power(&clear, "keck"), 20);
+
+        let stormy = Weather { clouds: 0.8, seeing: 0.7, humidity: 0.9, moonlight: 1.0 };
+        assert!(power(&stormy, "dobsonian_20in") < 18 / 2);
+        assert!(power(&stormy, "keck") > 18 * 3 / 4);
+
+        let awful = Weather { clouds: 1.0, seeing: 1.0, humidity: 1.0, moonlight: 1.0 };
+        assert_eq!(power(&awful, "eye"), 1);
+    }
+}
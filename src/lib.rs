@@ -1,15 +1,16 @@
-#![feature(hash_drain_filter)]
 #[macro_use] pub mod utils;
 mod draw;
-mod points;
-mod game;
+pub mod points;
+pub mod orbits;
+pub mod game;
+mod web;
 
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen(start)]
 pub fn start() {
     utils::set_panic_hook();
-    game::start();
+    web::start();
 }
 
 #[wasm_bindgen]
@@ -0,0 +1,410 @@
+// Where the Sun, the Moon and the bright planets are, from the approximate elements in
+// Paul Schlyter's "How to compute planetary positions". Times are days since 2000 Jan 0.0 UT.
+
+use std::fmt;
+use std::str::FromStr;
+use anyhow::*;
+use crate::points::{Point, SkyCoord, mod360};
+
+// The Moon's orbit is measured in Earth radii, not AU
+const EARTH_RADIUS_AU: f64 = 4.2635e-5;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Body {
+    Sun,
+    Moon,
+    Mercury,
+    Venus,
+    Mars,
+    Jupiter,
+    Saturn,
+}
+
+impl Body {
+    pub const ALL: [Body; 7] = [Body::Sun, Body::Moon, Body::Mercury, Body::Venus, Body::Mars, Body::Jupiter, Body::Saturn];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Body::Sun => "sun",
+            Body::Moon => "moon",
+            Body::Mercury => "mercury",
+            Body::Venus => "venus",
+            Body::Mars => "mars",
+            Body::Jupiter => "jupiter",
+            Body::Saturn => "saturn",
+        }
+    }
+
+    /// Whether it shows phases from Earth. The outer planets always look nearly full.
+    pub fn has_phases(&self) -> bool {
+        matches!(self, Body::Moon | Body::Mercury | Body::Venus)
+    }
+}
+
+impl FromStr for Body {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Body::ALL.iter()
+            .find(|body| body.key() == s)
+            .cloned()
+            .with_context(|| {
+                let keys: Vec<&str> = Body::ALL.iter().map(|body| body.key()).collect();
+                format!("unknown body \"{}\" (expected one of {})", s, keys.join(", "))
+            })
+    }
+}
+
+/// The day number at midnight UT at the start of a "YYYY-MM-DD" date
+pub fn day_number(date: &str) -> Result<f64> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let (y, m, d) = match parts[..] {
+        [y, m, d] => (y.parse::<i64>()?, m.parse::<i64>()?, d.parse::<i64>()?),
+        _ => bail!("dates should look like 2000-01-31, not \"{}\"", date)
+    };
+    if !(1900..=2100).contains(&y) || !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        bail!("\"{}\" isn't a date between 1900 and 2100", date);
+    }
+    Ok((367 * y - 7 * (y + (m + 9) / 12) / 4 + 275 * m / 9 + d - 730530) as f64)
+}
+
+/// The other way around: the date and time (UT) of a day number, like "2000-01-31 18:00"
+pub fn date(day: f64) -> String {
+    // To the nearest minute, then days since 1970-01-01, then the civil calendar
+    // from those (Howard Hinnant's algorithm)
+    let minutes = (day * 24.0 * 60.0).round() as i64;
+    let days = minutes.div_euclid(24 * 60) + 10956;
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+
+    let minutes = minutes.rem_euclid(24 * 60);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", y, m, d, minutes / 60, minutes % 60)
+}
+
+/// Where a body is in the sky, as seen from the center of the Earth
+pub fn position(body: Body, day: f64) -> SkyCoord {
+    let ecliptic = geocentric(body, day);
+    let obliquity = (23.4393 - 3.563e-7 * day).to_radians();
+
+    // Tip the ecliptic up to the celestial equator, around the line to the equinox (x)
+    let yz = rotate(Point::xy(ecliptic.xy.y, ecliptic.z), obliquity);
+    let equator = Point::xy(ecliptic.xy.x, yz.x);
+    SkyCoord::radians(equator.th(), yz.y.atan2(equator.r()))
+}
+
+/// How much of a body's disc we see lit up, and whether that's growing
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Phase {
+    pub illuminated: f64, // from 0 (new) to 1 (full)
+    pub waxing: bool
+}
+
+pub fn phase(body: Body, day: f64) -> Phase {
+    let lit = |day: f64| {
+        if body == Body::Sun {
+            return 1.0;
+        }
+        // The angle at the body between the Sun and us
+        let (us, sun) = (geocentric(body, day), geocentric(Body::Sun, day));
+        let angle = sun.minus(us).angle_to(us.scale(-1.0));
+        (1.0 + angle.cos()) / 2.0
+    };
+    let illuminated = lit(day);
+    Phase { illuminated, waxing: lit(day + 0.1) > illuminated }
+}
+
+/// When the Moon's next new, as a day number
+pub fn next_new_moon(day: f64) -> f64 {
+    // How far the Moon's got ahead of the Sun around the ecliptic. It's new when that goes back to 0
+    let ahead = |day: f64| mod360(geocentric(Body::Moon, day).longitude() - geocentric(Body::Sun, day).longitude());
+    const STEP: f64 = 0.5;
+    let mut from = day;
+    while ahead(from + STEP) > ahead(from) {
+        from += STEP;
+    }
+    // Then narrow it down
+    let (mut before, mut after) = (from, from + STEP);
+    for _ in 0..20 {
+        let middle = (before + after) / 2.0;
+        if ahead(middle) > ahead(before) {
+            before = middle;
+        } else {
+            after = middle;
+        }
+    }
+    after
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match (self.illuminated, self.waxing) {
+            (x, _) if x < 0.03 => "new",
+            (x, _) if x > 0.97 => "full",
+            (x, true) if (0.45..=0.55).contains(&x) => "first quarter",
+            (x, false) if (0.45..=0.55).contains(&x) => "last quarter",
+            (x, true) if x < 0.5 => "waxing crescent",
+            (x, false) if x < 0.5 => "waning crescent",
+            (_, true) => "waxing gibbous",
+            (_, false) => "waning gibbous",
+        };
+        f.write_str(name)
+    }
+}
+
+// Orbital elements at some moment, with angles in degrees
+struct Elements {
+    node: f64, // longitude of the ascending node
+    inclination: f64,
+    perihelion: f64, // argument of the perihelion
+    a: f64, // semi-major axis: AU, or Earth radii for the Moon
+    e: f64, // eccentricity
+    anomaly: f64, // mean anomaly
+}
+
+fn elements(body: Body, d: f64) -> Elements {
+    let el = |node: f64, inclination: f64, perihelion: f64, a: f64, e: f64, anomaly: f64| {
+        Elements { node, inclination, perihelion, a, e, anomaly }
+    };
+    match body {
+        // The Sun's apparent orbit around us, which is the Earth's around it turned inside out
+        Body::Sun => el(
+            0.0, 0.0, 282.9404 + 4.70935e-5 * d,
+            1.0, 0.016709 - 1.151e-9 * d, 356.0470 + 0.985_600_258_5 * d
+        ),
+        Body::Moon => el(
+            125.1228 - 0.052_953_808_3 * d, 5.1454, 318.0634 + 0.164_357_322_3 * d,
+            60.2666, 0.054900, 115.3654 + 13.064_992_950_9 * d
+        ),
+        Body::Mercury => el(
+            48.3313 + 3.24587e-5 * d, 7.0047 + 5.00e-8 * d, 29.1241 + 1.01444e-5 * d,
+            0.387098, 0.205635 + 5.59e-10 * d, 168.6562 + 4.092_334_436_8 * d
+        ),
+        Body::Venus => el(
+            76.6799 + 2.46590e-5 * d, 3.3946 + 2.75e-8 * d, 54.8910 + 1.38374e-5 * d,
+            0.723330, 0.006773 - 1.302e-9 * d, 48.0052 + 1.602_130_224_4 * d
+        ),
+        Body::Mars => el(
+            49.5574 + 2.11081e-5 * d, 1.8497 - 1.78e-8 * d, 286.5016 + 2.92961e-5 * d,
+            1.523688, 0.093405 + 2.516e-9 * d, 18.6021 + 0.524_020_776_6 * d
+        ),
+        Body::Jupiter => el(
+            100.4542 + 2.76854e-5 * d, 1.3030 - 1.557e-7 * d, 273.8777 + 1.64505e-5 * d,
+            5.20256, 0.048498 + 4.469e-9 * d, 19.8950 + 0.083_085_300_1 * d
+        ),
+        Body::Saturn => el(
+            113.6634 + 2.38980e-5 * d, 2.4886 - 1.081e-7 * d, 339.3939 + 2.97661e-5 * d,
+            9.55475, 0.055546 - 9.499e-9 * d, 316.9670 + 0.033_444_228_2 * d
+        ),
+    }
+}
+
+// A position in ecliptic coordinates: xy in the plane of the Earth's orbit, with
+// x towards the March equinox, and z up out of it
+#[derive(Debug, Copy, Clone)]
+struct Ecliptic {
+    xy: Point,
+    z: f64
+}
+
+impl Ecliptic {
+    fn spherical(longitude: f64, latitude: f64, distance: f64) -> Self {
+        Self { xy: Point::rth(distance * latitude.cos(), longitude), z: distance * latitude.sin() }
+    }
+
+    fn distance(&self) -> f64 {
+        self.xy.r().hypot(self.z)
+    }
+
+    fn longitude(&self) -> f64 {
+        self.xy.th()
+    }
+
+    fn latitude(&self) -> f64 {
+        self.z.atan2(self.xy.r())
+    }
+
+    fn plus(self, other: Self) -> Self {
+        Self { xy: self.xy + other.xy, z: self.z + other.z }
+    }
+
+    fn minus(self, other: Self) -> Self {
+        Self { xy: self.xy - other.xy, z: self.z - other.z }
+    }
+
+    fn scale(self, c: f64) -> Self {
+        Self { xy: self.xy.scale(c), z: self.z * c }
+    }
+
+    fn angle_to(&self, other: Self) -> f64 {
+        let dot = self.xy.x * other.xy.x + self.xy.y * other.xy.y + self.z * other.z;
+        (dot / (self.distance() * other.distance())).clamp(-1.0, 1.0).acos()
+    }
+}
+
+fn rotate(p: Point, angle: f64) -> Point {
+    Point::rth(p.r(), p.th() + angle)
+}
+
+// Where something is relative to whatever it orbits
+fn orbit(el: &Elements) -> Ecliptic {
+    // Kepler's equation, by Newton's method: from the mean anomaly to the eccentric anomaly
+    let (m, e) = (el.anomaly.to_radians(), el.e);
+    let mut ecc = m + e * m.sin() * (1.0 + e * m.cos());
+    for _ in 0..5 {
+        ecc -= (ecc - e * ecc.sin() - m) / (1.0 - e * ecc.cos());
+    }
+
+    // In the plane of the orbit, with the perihelion along x
+    let plane = Point::xy(el.a * (ecc.cos() - e), el.a * (1.0 - e * e).sqrt() * ecc.sin());
+
+    // Measured from the ascending node instead, then tilted and turned to face the equinox
+    let from_node = rotate(plane, el.perihelion.to_radians());
+    let (sin_i, cos_i) = el.inclination.to_radians().sin_cos();
+    Ecliptic {
+        xy: rotate(Point::xy(from_node.x, from_node.y * cos_i), el.node.to_radians()),
+        z: from_node.y * sin_i
+    }
+}
+
+// The Moon's ellipse, plus the biggest ways the Sun pulls it around
+fn moon(d: f64) -> Ecliptic {
+    let (moon, sun) = (elements(Body::Moon, d), elements(Body::Sun, d));
+    let ellipse = orbit(&moon);
+
+    let (ms, mm) = (sun.anomaly, moon.anomaly);
+    let elongation = (mm + moon.perihelion + moon.node) - (ms + sun.perihelion);
+    let latitude_arg = mm + moon.perihelion;
+    let sin = |degrees: f64| degrees.to_radians().sin();
+    let cos = |degrees: f64| degrees.to_radians().cos();
+    let (d2, f) = (2.0 * elongation, latitude_arg);
+
+    let longitude = -1.274 * sin(mm - d2) // the evection
+        + 0.658 * sin(d2) // the variation
+        - 0.186 * sin(ms) // the yearly equation
+        - 0.059 * sin(2.0 * mm - d2)
+        - 0.057 * sin(mm - d2 + ms)
+        + 0.053 * sin(mm + d2)
+        + 0.046 * sin(d2 - ms)
+        + 0.041 * sin(mm - ms)
+        - 0.035 * sin(elongation)
+        - 0.031 * sin(mm + ms);
+    let latitude = -0.173 * sin(f - d2)
+        - 0.055 * sin(mm - f - d2)
+        - 0.046 * sin(mm + f - d2)
+        + 0.033 * sin(f + d2)
+        + 0.017 * sin(2.0 * mm + f);
+    let distance = ellipse.distance() - 0.58 * cos(mm - d2) - 0.46 * cos(d2);
+
+    Ecliptic::spherical(
+        ellipse.longitude() + longitude.to_radians(),
+        ellipse.latitude() + latitude.to_radians(),
+        distance * EARTH_RADIUS_AU
+    )
+}
+
+// Where a body is relative to the Earth, in AU
+fn geocentric(body: Body, d: f64) -> Ecliptic {
+    match body {
+        Body::Sun => orbit(&elements(Body::Sun, d)),
+        Body::Moon => moon(d),
+        planet => orbit(&elements(planet, d)).plus(geocentric(Body::Sun, d)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::points::mod360_symmetric;
+
+    // A moment in time, UT
+    fn at(date: &str, hours: f64) -> f64 {
+        day_number(date).unwrap() + hours / 24.0
+    }
+
+    fn separation(a: Body, b: Body, day: f64) -> f64 {
+        position(a, day).separation(position(b, day)).to_degrees()
+    }
+
+    #[test]
+    fn day_numbers() {
+        assert_eq!(day_number("2000-01-01").unwrap(), 1.0);
+        assert_eq!(day_number("1990-04-19").unwrap(), -3543.0);
+        assert_eq!(day_number("2000-03-01").unwrap() - day_number("2000-02-28").unwrap(), 2.0);
+        assert!(day_number("2000-13-01").is_err());
+        assert!(day_number("yesterday").is_err());
+
+        assert_eq!(date(1.0), "2000-01-01 00:00");
+        assert_eq!(date(at("2017-08-21", 18.5)), "2017-08-21 18:30");
+        for date_string in ["1999-12-31", "2000-02-29", "2024-03-01", "2025-09-01"].iter() {
+            assert_eq!(date(day_number(date_string).unwrap()), format!("{} 00:00", date_string));
+        }
+    }
+
+    #[test]
+    fn the_sun_through_the_year() {
+        // The March equinox and the June solstice in 2000
+        let equinox = position(Body::Sun, at("2000-03-20", 7.6));
+        assert!(mod360_symmetric(equinox.ra).to_degrees().abs() < 0.1, "{:?}", equinox);
+        assert!(equinox.dec.to_degrees().abs() < 0.05, "{:?}", equinox);
+
+        let solstice = position(Body::Sun, at("2000-06-21", 1.8));
+        assert!((solstice.ra.to_degrees() - 90.0).abs() < 0.1, "{:?}", solstice);
+        assert!((solstice.dec.to_degrees() - 23.44).abs() < 0.05, "{:?}", solstice);
+    }
+
+    #[test]
+    fn eclipses() {
+        // The Great American Eclipse: the Moon right in front of the Sun
+        let eclipse = at("2017-08-21", 18.43);
+        assert!(separation(Body::Moon, Body::Sun, eclipse) < 1.0);
+        assert_eq!(phase(Body::Moon, eclipse).to_string(), "new");
+
+        // The total lunar eclipse of January 2000: right opposite it, in the Earth's shadow
+        let eclipse = at("2000-01-21", 4.73);
+        assert!(separation(Body::Moon, Body::Sun, eclipse) > 179.0);
+        assert_eq!(phase(Body::Moon, eclipse).to_string(), "full");
+
+        // And a week before that, half lit and growing
+        assert_eq!(phase(Body::Moon, at("2000-01-14", 13.6)).to_string(), "first quarter");
+    }
+
+    #[test]
+    fn new_moons() {
+        let close_to = |day: f64, expected: f64| (day - expected).abs() < 0.25;
+        assert!(close_to(next_new_moon(at("2000-01-01", 0.0)), at("2000-01-06", 18.2)));
+        assert!(close_to(next_new_moon(at("2017-08-10", 0.0)), at("2017-08-21", 18.5)));
+
+        // Right after one, the next is a whole month away
+        let day = next_new_moon(at("2017-08-10", 0.0)) + 0.1;
+        assert!(close_to(next_new_moon(day), at("2017-09-20", 5.5)));
+    }
+
+    #[test]
+    fn planets() {
+        // Jupiter and Saturn's great conjunction, low in Capricornus
+        let conjunction = at("2020-12-21", 18.0);
+        assert!(separation(Body::Jupiter, Body::Saturn, conjunction) < 0.3);
+        assert!(position(Body::Jupiter, conjunction).separation(SkyCoord::degrees(302.0, -20.4)).to_degrees() < 1.0);
+
+        // Mars at opposition
+        assert!(separation(Body::Mars, Body::Sun, at("2020-10-13", 23.0)) > 175.0);
+
+        // Venus crossing the face of the Sun, and then at its greatest elongation in the evening
+        let transit = at("2012-06-06", 1.5);
+        assert!(separation(Body::Venus, Body::Sun, transit) < 0.5);
+        assert!(phase(Body::Venus, transit).illuminated < 0.01);
+
+        let elongation = at("2020-03-24", 22.0);
+        assert!((separation(Body::Venus, Body::Sun, elongation) - 46.1).abs() < 0.5);
+        let venus = phase(Body::Venus, elongation);
+        assert!((venus.illuminated - 0.5).abs() < 0.05, "{:?}", venus);
+        assert!(!venus.waxing);
+    }
+}
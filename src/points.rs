@@ -141,6 +141,187 @@ impl Point {
     pub fn th(&self) -> f64 {
         mod360(self.y.atan2(self.x))
     }
+
+    pub fn distance(&self, other: Point) -> f64 {
+        (*self - other).r()
+    }
+}
+
+/// Hit-testing: out of some items with positions, find the one closest to the target,
+/// as long as it's within the given radius.
+pub fn nearest_within<T, I>(target: Point, radius: f64, items: I) -> Option<T>
+    where I: IntoIterator<Item = (Point, T)>
+{
+    items.into_iter()
+        .map(|(p, item)| (target.distance(p), item))
+        .filter(|(d, _)| *d <= radius)
+        .min_by(|(d0, _), (d1, _)| d0.partial_cmp(d1).unwrap())
+        .map(|(_, item)| item)
+}
+
+/// A direction on the celestial sphere: right ascension and declination, in radians.
+/// (It works just as well for any other longitude/latitude pair, like azimuth/altitude.)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SkyCoord {
+    pub ra: f64,
+    pub dec: f64
+}
+
+impl SkyCoord {
+    pub fn radians(ra: f64, dec: f64) -> Self {
+        Self { ra, dec }
+    }
+
+    /// Astronomers usually give coordinates in degrees
+    pub fn degrees(ra: f64, dec: f64) -> Self {
+        Self { ra: ra.to_radians(), dec: dec.to_radians() }
+    }
+
+    /// The angle between two directions, in radians. This is the vincenty formula,
+    /// which (unlike the simpler arccos one) stays accurate for tiny and huge angles.
+    pub fn separation(&self, other: SkyCoord) -> f64 {
+        let d_ra = other.ra - self.ra;
+        let (sin_dec1, cos_dec1) = self.dec.sin_cos();
+        let (sin_dec2, cos_dec2) = other.dec.sin_cos();
+        let a = cos_dec2 * d_ra.sin();
+        let b = cos_dec1 * sin_dec2 - sin_dec1 * cos_dec2 * d_ra.cos();
+        let c = sin_dec1 * sin_dec2 + cos_dec1 * cos_dec2 * d_ra.cos();
+        (a * a + b * b).sqrt().atan2(c)
+    }
+
+    /// The point a fraction t of the way from here to there, along the great circle
+    /// between them. Opposite points have no single great circle, so then this just
+    /// gives whichever end t is closer to.
+    pub fn interpolate(&self, other: SkyCoord, t: f64) -> SkyCoord {
+        let angle = self.separation(other);
+        let (from, to) = (self.to_vector(), other.to_vector());
+        if angle.sin().abs() < 1e-12 {
+            return if t < 0.5 { *self } else { other };
+        }
+        let a = ((1.0 - t) * angle).sin() / angle.sin();
+        let b = (t * angle).sin() / angle.sin();
+        SkyCoord::from_vector([
+            a * from[0] + b * to[0],
+            a * from[1] + b * to[1],
+            a * from[2] + b * to[2],
+        ])
+    }
+
+    // Unit vector, with z towards the north pole and x towards ra = 0
+    fn to_vector(self) -> [f64; 3] {
+        let (sin_dec, cos_dec) = self.dec.sin_cos();
+        [cos_dec * self.ra.cos(), cos_dec * self.ra.sin(), sin_dec]
+    }
+
+    fn from_vector(v: [f64; 3]) -> SkyCoord {
+        let r = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        SkyCoord {
+            ra: mod360(v[1].atan2(v[0])),
+            dec: (v[2] / r).clamp(-1.0, 1.0).asin()
+        }
+    }
+}
+
+/// Ways of flattening part of the sphere onto a plane. On the plane, x points east
+/// and y points north, and a point at angle c from the center ends up at distance:
+///   stereographic: 2 tan(c/2), which keeps shapes right (good for wide views)
+///   gnomonic: tan(c), which keeps great circles straight (good for eyepieces)
+///   orthographic: sin(c), which looks like a globe seen from far away
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    Stereographic,
+    Gnomonic,
+    Orthographic
+}
+
+impl Projection {
+    /// Project a point onto the plane tangent to the sphere at center. None if this
+    /// projection can't show it (for gnomonic and orthographic, anything 90deg or more away).
+    pub fn forward(&self, center: SkyCoord, coord: SkyCoord) -> Option<Point> {
+        let d_ra = coord.ra - center.ra;
+        let (sin_dec0, cos_dec0) = center.dec.sin_cos();
+        let (sin_dec, cos_dec) = coord.dec.sin_cos();
+
+        // cos of the angle between the center and the point
+        let cos_c = sin_dec0 * sin_dec + cos_dec0 * cos_dec * d_ra.cos();
+        let k = match self {
+            Projection::Stereographic if cos_c > -1.0 + 1e-12 => 2.0 / (1.0 + cos_c),
+            Projection::Gnomonic if cos_c > 1e-12 => 1.0 / cos_c,
+            Projection::Orthographic if cos_c >= 0.0 => 1.0,
+            _ => return None
+        };
+        Some(Point::xy(
+            k * cos_dec * d_ra.sin(),
+            k * (cos_dec0 * sin_dec - sin_dec0 * cos_dec * d_ra.cos())
+        ))
+    }
+
+    /// The point on the sphere that projects to p. None if nothing does (which only
+    /// happens for orthographic, outside the unit circle).
+    pub fn inverse(&self, center: SkyCoord, p: Point) -> Option<SkyCoord> {
+        let rho = p.r();
+        if rho < 1e-12 {
+            return Some(center);
+        }
+        let c = match self {
+            Projection::Stereographic => 2.0 * (rho / 2.0).atan(),
+            Projection::Gnomonic => rho.atan(),
+            Projection::Orthographic if rho <= 1.0 => rho.asin(),
+            Projection::Orthographic => return None
+        };
+        let (sin_c, cos_c) = c.sin_cos();
+        let (sin_dec0, cos_dec0) = center.dec.sin_cos();
+        let dec = (cos_c * sin_dec0 + p.y * sin_c * cos_dec0 / rho).clamp(-1.0, 1.0).asin();
+        let ra = center.ra + (p.x * sin_c).atan2(rho * cos_dec0 * cos_c - p.y * sin_dec0 * sin_c);
+        Some(SkyCoord::radians(mod360(ra), dec))
+    }
+
+    /// How far from the center a point at angle c ends up
+    pub fn radius_for(&self, c: f64) -> f64 {
+        match self {
+            Projection::Stereographic => 2.0 * (c / 2.0).tan(),
+            Projection::Gnomonic => c.tan(),
+            Projection::Orthographic => c.sin()
+        }
+    }
+}
+
+/// A window onto the sky, drawn on a canvas of the given size. The field of view is
+/// the angle across the width of the canvas. North is up and east is to the left,
+/// the way the sky looks when you're lying on your back.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SkyView {
+    pub center: SkyCoord,
+    pub fov: f64,
+    pub width: f64,
+    pub height: f64,
+    pub projection: Projection
+}
+
+impl SkyView {
+    /// Where a point in the sky lands on the canvas. Anything more than 90deg from
+    /// the center is behind the viewer, and gives None. It might still be off the
+    /// edge of the canvas.
+    pub fn project(&self, coord: SkyCoord) -> Option<Point> {
+        if self.center.separation(coord) > consts::FRAC_PI_2 {
+            return None;
+        }
+        let p = self.projection.forward(self.center, coord)?;
+        let scale = self.pixels_per_unit();
+        Some(Point::xy(self.width / 2.0 - p.x * scale, self.height / 2.0 - p.y * scale))
+    }
+
+    /// Which point in the sky is under a point on the canvas, like where the player clicked
+    pub fn unproject(&self, point: Point) -> Option<SkyCoord> {
+        let scale = self.pixels_per_unit();
+        let p = Point::xy((self.width / 2.0 - point.x) / scale, (self.height / 2.0 - point.y) / scale);
+        self.projection.inverse(self.center, p)
+    }
+
+    // The edges of the canvas are half the field of view from the center
+    fn pixels_per_unit(&self) -> f64 {
+        (self.width / 2.0) / self.projection.radius_for(self.fov / 2.0)
+    }
 }
 
 /// This is a better modulo function. (returns x mod n)
@@ -193,4 +374,149 @@ impl Anchor {
 pub const ANGLE_EAST: f64 = 0.0;
 pub const ANGLE_WEST: f64 = consts::PI;
 pub const ANGLE_NORTH: f64 = consts::PI / 2.0;
-pub const ANGLE_SOUTH: f64 = 3.0 * consts::PI / 2.0;
\ No newline at end of file
+pub const ANGLE_SOUTH: f64 = 3.0 * consts::PI / 2.0;
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECTIONS: [Projection; 3] = [Projection::Stereographic, Projection::Gnomonic, Projection::Orthographic];
+
+    fn view(projection: Projection) -> SkyView {
+        SkyView { center: SkyCoord::degrees(90.0, 0.0), fov: 60f64.to_radians(), width: 800.0, height: 600.0, projection }
+    }
+
+    fn assert_near(p: Point, q: Point) {
+        assert!(p.distance(q) < 1e-6, "{:?} isn't {:?}", p, q);
+    }
+
+    fn assert_same_place(a: SkyCoord, b: SkyCoord) {
+        assert!(a.separation(b) < 1e-9, "{:?} isn't {:?}", a, b);
+    }
+
+    fn degrees_apart(a: SkyCoord, b: SkyCoord) -> f64 {
+        a.separation(b).to_degrees()
+    }
+
+    #[test]
+    fn hit_testing() {
+        let items = [(Point::xy(100.0, 100.0), "a"), (Point::xy(120.0, 100.0), "b")];
+        let nearest = |x, y| nearest_within(Point::xy(x, y), 10.0, items.iter().copied());
+
+        // Inside the radius, on its edge, and just outside
+        assert_eq!(nearest(100.0, 106.0), Some("a"));
+        assert_eq!(nearest(90.0, 100.0), Some("a"));
+        assert_eq!(nearest(89.9, 100.0), None);
+        assert_eq!(nearest(100.0, 110.1), None);
+
+        // In between two, the nearer one wins
+        assert_eq!(nearest(109.0, 100.0), Some("a"));
+        assert_eq!(nearest(111.0, 100.0), Some("b"));
+        assert_eq!(nearest(118.0, 95.0), Some("b"));
+
+        // Nowhere near anything, or nothing to hit
+        assert_eq!(nearest(300.0, 300.0), None);
+        assert_eq!(nearest_within(Point::xy(100.0, 100.0), 10.0, Vec::<(Point, &str)>::new()), None);
+    }
+
+    #[test]
+    fn angular_separation() {
+        let sirius = SkyCoord::degrees(101.287, -16.716);
+        let betelgeuse = SkyCoord::degrees(88.793, 7.407);
+        let m31 = SkyCoord::degrees(10.685, 41.269);
+        assert!((degrees_apart(sirius, betelgeuse) - 27.104).abs() < 0.001);
+        assert!((degrees_apart(sirius, m31) - 101.378).abs() < 0.001);
+
+        assert!((degrees_apart(SkyCoord::degrees(0.0, 0.0), SkyCoord::degrees(90.0, 0.0)) - 90.0).abs() < 1e-9);
+        assert!((degrees_apart(SkyCoord::degrees(0.0, 90.0), SkyCoord::degrees(123.0, -90.0)) - 180.0).abs() < 1e-9);
+        assert!((degrees_apart(SkyCoord::degrees(359.5, 0.0), SkyCoord::degrees(0.5, 0.0)) - 1.0).abs() < 1e-9);
+        assert_eq!(m31.separation(m31), 0.0);
+    }
+
+    #[test]
+    fn great_circle_interpolation() {
+        let a = SkyCoord::degrees(0.0, 0.0);
+        let b = SkyCoord::degrees(90.0, 0.0);
+        assert_same_place(a.interpolate(b, 0.0), a);
+        assert_same_place(a.interpolate(b, 1.0), b);
+        assert_same_place(a.interpolate(b, 0.5), SkyCoord::degrees(45.0, 0.0));
+        assert_same_place(a.interpolate(SkyCoord::degrees(0.0, 90.0), 0.5), SkyCoord::degrees(0.0, 45.0));
+
+        // Going the short way round, across ra = 0
+        assert_same_place(SkyCoord::degrees(350.0, 0.0).interpolate(SkyCoord::degrees(10.0, 0.0), 0.5), a);
+
+        // Every step is the same fraction of the way
+        let sirius = SkyCoord::degrees(101.287, -16.716);
+        let m31 = SkyCoord::degrees(10.685, 41.269);
+        let quarter = sirius.interpolate(m31, 0.25);
+        assert!((quarter.separation(sirius) - 0.25 * sirius.separation(m31)).abs() < 1e-9);
+        assert!((quarter.separation(m31) - 0.75 * sirius.separation(m31)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn projections_of_known_points() {
+        let center = SkyCoord::degrees(0.0, 0.0);
+        let east = SkyCoord::degrees(45.0, 0.0);
+        let north = SkyCoord::degrees(0.0, 45.0);
+        let expected = [
+            (Projection::Stereographic, 2.0 * 22.5f64.to_radians().tan()),
+            (Projection::Gnomonic, 1.0),
+            (Projection::Orthographic, 0.5f64.sqrt()),
+        ];
+        for (projection, r) in expected.iter() {
+            assert_near(projection.forward(center, center).unwrap(), Point::xy(0.0, 0.0));
+            assert_near(projection.forward(center, east).unwrap(), Point::xy(*r, 0.0));
+            assert_near(projection.forward(center, north).unwrap(), Point::xy(0.0, *r));
+            assert!((projection.radius_for(45f64.to_radians()) - r).abs() < 1e-12);
+        }
+
+        // Only stereographic can show the far side
+        let behind = SkyCoord::degrees(180.0, 10.0);
+        assert!(Projection::Stereographic.forward(center, behind).is_some());
+        assert_eq!(Projection::Gnomonic.forward(center, behind), None);
+        assert_eq!(Projection::Orthographic.forward(center, behind), None);
+        assert_eq!(Projection::Orthographic.inverse(center, Point::xy(1.0, 1.0)), None);
+    }
+
+    #[test]
+    fn projections_round_trip() {
+        let centers = [SkyCoord::degrees(0.0, 0.0), SkyCoord::degrees(101.287, -16.716), SkyCoord::degrees(250.0, 80.0)];
+        for projection in PROJECTIONS.iter() {
+            for center in centers.iter() {
+                for ra in (0..360).step_by(15) {
+                    for dec in (-75..=75).step_by(15) {
+                        let coord = SkyCoord::degrees(ra as f64, dec as f64);
+                        if center.separation(coord) > 80f64.to_radians() {
+                            continue;
+                        }
+                        let p = projection.forward(*center, coord).unwrap();
+                        assert_same_place(projection.inverse(*center, p).unwrap(), coord);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sky_projection() {
+        for projection in PROJECTIONS.iter() {
+            let view = view(*projection);
+            assert_near(view.project(view.center).unwrap(), Point::xy(400.0, 300.0));
+
+            // Half the field of view away, east or west, is the edge of the canvas
+            assert_near(view.project(SkyCoord::degrees(120.0, 0.0)).unwrap(), Point::xy(0.0, 300.0));
+            assert_near(view.project(SkyCoord::degrees(60.0, 0.0)).unwrap(), Point::xy(800.0, 300.0));
+
+            // North is up
+            assert!(view.project(SkyCoord::degrees(90.0, 10.0)).unwrap().y < 300.0);
+
+            // Behind the viewer
+            assert_eq!(view.project(SkyCoord::degrees(270.0, 0.0)), None);
+
+            // Clicking on something finds it again
+            let sirius = SkyCoord::degrees(101.287, -16.716);
+            assert_same_place(view.unproject(view.project(sirius).unwrap()).unwrap(), sirius);
+        }
+    }
+}
@@ -4,6 +4,7 @@ use web_sys::{
     HtmlElement,
     Element,
     HtmlCanvasElement,
+    UrlSearchParams,
     window
 };
 
@@ -28,7 +29,7 @@ pub fn get_document() -> Result<Document> {
 pub fn query_html(selector: &str) -> Result<HtmlElement> {
     let document = get_document()?;
     let element: Element = document.query_selector(selector)
-        .unwrap().context(format!("query_html: failed to call document.query_selector"))
+        .unwrap().context("query_html: failed to call document.query_selector".to_string())
         .unwrap();
 
     // TODO: better error handling
@@ -36,6 +37,28 @@ pub fn query_html(selector: &str) -> Result<HtmlElement> {
 
 }
 
+/// Look up a parameter in the page's query string, e.g. "seed" in "?seed=1234"
+pub fn query_param(name: &str) -> Option<String> {
+    let search = window()?.location().search().ok()?;
+    UrlSearchParams::new_with_str(&search).ok()?.get(name)
+}
+
+/// Make text safe to put in HTML, whether between tags or in an attribute
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
 pub fn get_canvas_by_id(canvas_id: String) -> Result<HtmlCanvasElement> {
     let document = get_document()?;
 
@@ -53,10 +76,18 @@ pub fn get_canvas_by_id(canvas_id: String) -> Result<HtmlCanvasElement> {
 #[macro_export]
 macro_rules! log {
     ( $( $t:tt )* ) => {
-        // Note: web_sys::console::log_1 is unsafe, but the macro doesn't recognize it
-        #[allow(unused_unsafe)]
-        unsafe {
-            web_sys::console::log_1(&format!( $( $t )* ).into());
-        }
+        web_sys::console::log_1(&format!( $( $t )* ).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_html_text() {
+        assert_eq!(escape_html("Barnard's Star"), "Barnard&#39;s Star");
+        assert_eq!(escape_html("<b>\"M&M\"</b>"), "&lt;b&gt;&quot;M&amp;M&quot;&lt;/b&gt;");
+        assert_eq!(escape_html("Sirius"), "Sirius");
     }
-}
\ No newline at end of file
+}
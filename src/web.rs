@@ -0,0 +1,685 @@
+// The browser front end: draws the sky, runs the game loop and saves to localStorage
+
+use anyhow::{anyhow, Context, Result};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use wasm_bindgen::{JsCast};
+use gloo_timers::future::TimeoutFuture;
+use std::rc::Rc;
+use std::cell::RefCell;
+use rand::prelude::*;
+use rand::rngs::SmallRng;
+use rand::distributions::WeightedIndex;
+use super::draw::*;
+use super::points::{Point, SkyCoord, SkyView, Projection};
+use super::orbits::{self, Phase};
+use super::game::{self, Game};
+use super::game::platform::{Platform, Logger, Clock};
+use super::game::save::Storage;
+use super::game::seed::{self, Seed};
+use super::game::economy;
+use super::game::away;
+use super::game::sky;
+use super::game::field;
+use super::game::category::{self, Sprite, Shape};
+use web_sys::{Element, EventTarget, MouseEvent};
+use gloo_events::*;
+use super::utils;
+
+// Game settings
+const GAME_CANVAS_WIDTH: u32 = 800;
+const GAME_CANVAS_HEIGHT: u32 = 600;
+
+const GAME_CANVAS_ID: &str = "game-canvas";
+
+const COLOR_SKY: &str = "#08011a";
+const COLOR_GROUND_AT_DUSK: &str = "#24201a";
+
+const COLOR_CLOUD: &str = "#6b6878";
+const COLOR_MOONLIGHT: &str = "#5a6a96";
+const COLOR_EYEPIECE_RIM: &str = "#3a3548";
+
+// The telescope view that opens where the player clicks
+const EYEPIECE_RADIUS: f64 = 130.0;
+const EYEPIECE_SPRITE_SCALE: f64 = 2.5;
+
+const HUD_SELECTOR: &str = "#hud";
+const JOURNAL_SELECTOR: &str = "#journal";
+
+// How many of the latest journal entries to show under the sky
+const JOURNAL_SHOWN: usize = 10;
+const CURSOR_SELECTOR: &str = "#eye-cursor";
+const TELESCOPE_SELECTOR: &str = "#telescope";
+
+// Add ?seed=1234 to the URL for a reproducible game
+const SEED_QUERY_PARAM: &str = "seed";
+
+pub fn start() {
+    utils::set_panic_hook();
+    configure_canvas(GAME_CANVAS_ID.to_string(), GAME_CANVAS_WIDTH, GAME_CANVAS_HEIGHT);
+    let draw = Draw::from_canvas_id(GAME_CANVAS_ID.to_string()).unwrap();
+
+    let url_seed = utils::query_param(SEED_QUERY_PARAM)
+        .and_then(|seed| seed.parse::<u64>().ok())
+        .map(Seed);
+    let game = load_or_start_game(url_seed);
+    let seed = game.seed;
+    let game = Rc::new(RefCell::new(game));
+
+    let stars = Rc::new(make_stars(&mut seed.stream(seed::STARFIELD), 2000));
+    let clouds = Rc::new(make_clouds(&mut seed.stream(seed::CLOUD_SHAPES), 40));
+
+    let eyepiece: Rc<RefCell<Option<Eyepiece>>> = Rc::new(RefCell::new(None));
+
+    let canvas = EventTarget::from(utils::query_html(&format!("#{}", GAME_CANVAS_ID)).unwrap());
+
+    // Clicking the sky observes whatever's there, and zooms in on it. Clicking the
+    // zoomed view puts it away again
+    let clicked_game = game.clone();
+    let clicked_eyepiece = eyepiece.clone();
+    let on_click = EventListener::new(&canvas, "click", move |event| {
+        let event = event.dyn_ref::<MouseEvent>().unwrap_throw();
+        let mouse_point = Point::xy(event.layer_x().into(), event.layer_y().into());
+        let mut eyepiece = clicked_eyepiece.borrow_mut();
+        if eyepiece.as_ref().is_some_and(|e| e.screen.distance(mouse_point) <= EYEPIECE_RADIUS) {
+            *eyepiece = None;
+            return;
+        }
+        log!("Observing a spot in the sky: {} {}", mouse_point.x, mouse_point.y);
+        let mut g = clicked_game.borrow_mut();
+        g.observe_at(mouse_point);
+        *eyepiece = Eyepiece::at(&g, mouse_point);
+    });
+
+    let cursor = utils::query_html(CURSOR_SELECTOR).unwrap();
+
+    let move_cursor_icon = EventListener::new(&canvas, "mousemove", move |event| {
+        let event = event.dyn_ref::<MouseEvent>().unwrap_throw();
+        let mouse_point = Point::xy(event.layer_x().into(), event.layer_y().into());
+        let css = cursor.style();
+        css.set_property("top", &format!("{}px", mouse_point.y - 20.0)).ok();
+        css.set_property("left", &format!("{}px", mouse_point.x - 20.0)).ok();
+    });
+
+    // The HUD's buttons are redrawn every tick, so listen for their clicks on the HUD itself
+    let hud = EventTarget::from(utils::query_html(HUD_SELECTOR).unwrap());
+    let hud_game = game.clone();
+    let on_hud_click = EventListener::new(&hud, "click", move |event| {
+        let button = match event.target().and_then(|target| target.dyn_into::<Element>().ok()) {
+            Some(button) => button,
+            None => return
+        };
+        let mut g = hud_game.borrow_mut();
+        let result = if let Some(key) = button.get_attribute("data-use") {
+            g.use_telescope(&key)
+        }
+        else if let Some(key) = button.get_attribute("data-buy") {
+            g.buy_telescope(&key)
+        }
+        else if let Some(key) = button.get_attribute("data-buy-site") {
+            g.buy_site(&key)
+        }
+        else if let Some(key) = button.get_attribute("data-buy-accessory") {
+            g.buy_accessory(&key)
+        }
+        else {
+            return;
+        };
+        if let Err(e) = result {
+            log!("> {}", e);
+        }
+        render_hud(&g);
+        render_cursor(&g);
+    });
+
+    // let these event listeners outlive this function
+    on_click.forget();
+    move_cursor_icon.forget();
+    on_hud_click.forget();
+
+    main_loop(game, Rc::new(draw), stars, clouds, eyepiece);
+}
+
+// The game is shared with the click handler, so the loop borrows it one tick at a time.
+// The sky turns a little every tick, so it all gets redrawn
+fn main_loop(
+    game: Rc<RefCell<Game>>,
+    draw: Rc<Draw>,
+    stars: Rc<Vec<Star>>,
+    clouds: Rc<Vec<Cloud>>,
+    eyepiece: Rc<RefCell<Option<Eyepiece>>>
+) {
+    {
+        let mut g = game.borrow_mut();
+        g.tick();
+        draw_background(&draw, &g);
+        draw_stars(&draw, &g, &stars);
+        draw_objects(&draw, &g);
+        draw_clouds(&draw, &g, &clouds);
+        draw_ground(&draw, &g);
+        if let Some(eyepiece) = eyepiece.borrow().as_ref() {
+            draw_eyepiece(&draw, &g, &stars, eyepiece);
+        }
+        render_hud(&g);
+        render_cursor(&g);
+        render_journal(&g);
+
+        if !g.seeded {
+            if let Err(e) = LocalStorage::new().and_then(|mut storage| g.save_to(&mut storage)) {
+                log!("Couldn't save the game: {:#}", e);
+            }
+        }
+    }
+
+    spawn_local(async {
+        TimeoutFuture::new(game::TICK_MS).await;
+        main_loop(game, draw, stars, clouds, eyepiece);
+    });
+}
+
+fn web_platform() -> Platform {
+    Platform::new(Box::new(ConsoleLogger), Box::new(JsClock))
+}
+
+/// Logs to the browser console
+pub struct ConsoleLogger;
+
+impl Logger for ConsoleLogger {
+    fn log(&self, message: &str) {
+        log!("{}", message);
+    }
+}
+
+pub struct JsClock;
+
+impl Clock for JsClock {
+    fn now_ms(&self) -> f64 {
+        js_sys::Date::now()
+    }
+}
+
+/// The browser's localStorage
+pub struct LocalStorage {
+    storage: web_sys::Storage
+}
+
+impl LocalStorage {
+    pub fn new() -> Result<Self> {
+        let storage = web_sys::window()
+            .context("LocalStorage::new: couldn't find the HTML window object")?
+            .local_storage()
+            .map_err(|e| anyhow!("LocalStorage::new: {:?}", e))?
+            .context("LocalStorage::new: localStorage isn't available")?;
+        Ok(Self { storage })
+    }
+}
+
+impl Storage for LocalStorage {
+    fn load(&self, key: &str) -> Result<Option<String>> {
+        self.storage.get_item(key).map_err(|e| anyhow!("LocalStorage::load: {:?}", e))
+    }
+
+    fn store(&mut self, key: &str, value: &str) -> Result<()> {
+        self.storage.set_item(key, value).map_err(|e| anyhow!("LocalStorage::store: {:?}", e))
+    }
+}
+
+// A seeded game always starts from scratch, and isn't saved, so that it stays reproducible
+fn load_or_start_game(seed: Option<Seed>) -> Game {
+    if let Some(seed) = seed {
+        log!("Playing a seeded game ({}). Progress won't be saved.", seed.0);
+        let mut game = Game::new(seed, web_platform());
+        game.seeded = true;
+        game.init();
+        return game;
+    }
+
+    match LocalStorage::new().and_then(|storage| Game::load_from(&storage, web_platform())) {
+        Ok(Some(mut game)) => {
+            log!("Welcome back! Picking up at generation {}", game.generation);
+            if let Some(summary) = game.catch_up(away::MAX_AWAY_MS) {
+                log!("{}", summary);
+                if let Some(window) = web_sys::window() {
+                    window.alert_with_message(&summary.to_string()).ok();
+                }
+            }
+            return game;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            log!("Couldn't load your saved game, so starting a new one: {:#}", e);
+        }
+    }
+    let mut game = Game::new(Seed::from_entropy(), web_platform());
+    game.init();
+    game
+}
+
+// Photons, buttons to switch telescopes, and buttons for whatever's up for sale:
+// the next telescope and site, and accessories
+fn render_hud(game: &Game) {
+    let (hours, minutes) = game.state.observer.lst_hours();
+    let mut html = format!(
+        "<div>Photons: {} <small>(+{} per second)</small></div>\
+         <div><small>Sidereal time {:02}:{:02}</small></div>\
+         <div><small>{}</small></div>",
+        game.state.photons, economy::income(&game.state),
+        hours, minutes, utils::escape_html(&game.state.weather.to_string())
+    );
+
+    // Deep-sky things are best looked for around new moon, so say when that is
+    let (_, phase) = game.state.observer.moon();
+    let day = game.state.observer.day;
+    let new_moon = match (orbits::next_new_moon(day) - day).round() as i32 {
+        0 => "New moon tonight: a good time for galaxies and nebulae.".to_string(),
+        1 => "New moon tomorrow night.".to_string(),
+        nights => format!("New moon in {} nights.", nights)
+    };
+    html += &format!(
+        "<div><small>The Moon is {} ({}% lit). {}</small></div>",
+        phase, (phase.illuminated * 100.0).round(), new_moon
+    );
+    if let Some(scope) = game.state.telescope() {
+        html += &format!(
+            "<div><small>Using {}: resolving power {} of {}</small></div>",
+            utils::escape_html(&scope.name), game.state.effective_power(), scope.max_power
+        );
+    }
+    if let Some(site) = game.state.site() {
+        html += &format!("<div><small>Observing from {}</small></div>", utils::escape_html(&site.name));
+    }
+    for event in game.state.events.values() {
+        html += &format!(
+            "<div><small>In the sky for now: {}</small></div>",
+            utils::escape_html(&event.object_at(game.generation).name)
+        );
+    }
+    for scope in game.state.telescopes.iter().filter(|scope| game.state.active.as_ref() != Some(&scope.key)) {
+        html += &format!(
+            "<button data-use=\"{}\">Use {}</button>",
+            utils::escape_html(&scope.key), utils::escape_html(&scope.name)
+        );
+    }
+    if let Some(scope) = game.telescope_for_sale() {
+        let disabled = if game.state.photons < scope.price { " disabled" } else { "" };
+        html += &format!(
+            "<button data-buy=\"{}\"{}>Buy {} ({} photons)</button>",
+            utils::escape_html(&scope.key), disabled, utils::escape_html(&scope.name), scope.price
+        );
+    }
+    if let Some(site) = game.site_for_sale() {
+        let disabled = if game.state.photons < site.price { " disabled" } else { "" };
+        html += &format!(
+            "<button data-buy-site=\"{}\"{}>Move to {} ({} photons)</button>",
+            utils::escape_html(&site.key), disabled, utils::escape_html(&site.name), site.price
+        );
+    }
+    for acc in game.accessories_for_sale() {
+        let disabled = if game.state.photons < acc.price { " disabled" } else { "" };
+        html += &format!(
+            "<button data-buy-accessory=\"{}\"{}>Buy {} ({} photons)</button>",
+            utils::escape_html(&acc.key), disabled, utils::escape_html(&acc.name), acc.price
+        );
+    }
+    if let Ok(hud) = utils::query_html(HUD_SELECTOR) {
+        hud.set_inner_html(&html);
+    }
+}
+
+// The latest observations, how many there are of each kind and with each telescope,
+// and a link to download the whole journal
+fn render_journal(game: &Game) {
+    let journal = &game.state.journal;
+    let element = match utils::query_html(JOURNAL_SELECTOR) {
+        Ok(element) => element,
+        Err(_) => return
+    };
+    // It only changes when the player observes something. (Once it's full, it stays the
+    // same length, so look at the latest entry too)
+    let latest = journal.entries().last().map_or_else(String::new, |entry| {
+        format!("{} {} {} {}", journal.len(), entry.generation, entry.object, entry.level)
+    });
+    if element.get_attribute("data-latest").as_deref() == Some(latest.as_str()) {
+        return;
+    }
+    element.set_attribute("data-latest", &latest).ok();
+
+    let telescope_name = |key: &str| game.telescopes.get(key).map_or(key.to_string(), |scope| scope.name.clone());
+    let by_kind: Vec<String> = category::ALL.iter()
+        .map(|category| (category.behavior().name(), journal.for_category(category).count()))
+        .filter(|(_, n)| *n > 0)
+        .map(|(name, n)| format!("{} {}", n, name))
+        .collect();
+    let by_telescope: Vec<String> = game.state.telescopes.iter()
+        .map(|scope| (scope.name.as_str(), journal.with_telescope(&scope.key).count()))
+        .filter(|(_, n)| *n > 0)
+        .map(|(name, n)| format!("{} with {}", n, name))
+        .collect();
+
+    let mut html = format!(
+        "<div><b>Observing journal</b> <small>({} observations)</small></div>\
+         <div><small>{}</small></div>\
+         <div><small>{}</small></div>",
+        journal.len(), utils::escape_html(&by_kind.join(", ")), utils::escape_html(&by_telescope.join(", "))
+    );
+    for entry in journal.entries().iter().rev().take(JOURNAL_SHOWN) {
+        html += &format!(
+            "<div class=\"entry\"><small>{} UT: {}, with {}</small><br>{}</div>",
+            orbits::date(entry.day), utils::escape_html(&entry.name),
+            utils::escape_html(&telescope_name(&entry.telescope)), utils::escape_html(&entry.discoveries.join(" "))
+        );
+    }
+    if !journal.is_empty() {
+        let href = String::from(js_sys::encode_uri_component(&journal.export()));
+        html += &format!(
+            "<a download=\"deepfield-journal.tsv\" href=\"data:text/tab-separated-values;charset=utf-8,{}\">Export the journal</a>",
+            href
+        );
+    }
+    element.set_inner_html(&html);
+}
+
+// The cursor, and the telescope standing in the corner, show which telescope the player's using
+fn render_cursor(game: &Game) {
+    let scope = match game.state.telescope() {
+        Some(scope) => scope,
+        None => return
+    };
+    for selector in [CURSOR_SELECTOR, TELESCOPE_SELECTOR] {
+        if let Ok(image) = utils::query_html(selector) {
+            if image.get_attribute("src").as_deref() != Some(scope.image.as_str()) {
+                image.set_attribute("src", &scope.image).ok();
+            }
+        }
+    }
+}
+
+fn configure_canvas(canvas_id: String, width: u32, height: u32) {
+    let canvas = utils::get_canvas_by_id(canvas_id).unwrap();
+    canvas.set_width(width);
+    canvas.set_height(height);
+}
+
+// The sky's brighter where there's more light pollution, and when the Moon's up
+fn draw_background(draw: &Draw, game: &Game) {
+    let color = game.state.site().map_or(COLOR_SKY, |site| site.sky_color.as_str());
+    draw.fill_all(color.to_string());
+
+    let moonlight = game.state.weather.moonlight;
+    if moonlight > 0.0 {
+        let params = CanvasDrawParams::new()
+            .fill(COLOR_MOONLIGHT.to_string())
+            .do_not_stroke(true)
+            .global_alpha(0.35 * moonlight);
+        draw.rect(Point::xy(0.0, 0.0), Point::xy(GAME_CANVAS_WIDTH as f64, GAME_CANVAS_HEIGHT as f64), &params);
+    }
+}
+
+// The range of star sizes in make_stars
+const STAR_RADIUS_FAINTEST: f64 = 1.0;
+const STAR_RADIUS_BRIGHTEST: f64 = 3.0;
+
+// A background star. These are just for looks, so they aren't part of the game
+struct Star {
+    coords: SkyCoord,
+    radius: f64,
+    color: &'static str,
+    alpha: f64
+}
+
+// Scatter stars evenly over the whole sky, so they can turn with it
+fn make_stars(mut rng: &mut SmallRng, n: usize) -> Vec<Star> {
+    // star magnitude distributions
+    let star_mag_buckets = [
+        (1.0, 0.262),
+        (1.2, 0.194),
+        (1.4, 0.144),
+        (1.6, 0.106),
+        (1.8, 0.079),
+        (2.0, 0.058),
+        (2.2, 0.043),
+        (2.4, 0.032),
+        (2.6, 0.024),
+        (2.8, 0.018),
+        (3.0, 0.013),
+        // (3.2, 0.010),
+        // (3.4, 0.007),
+        // (3.6, 0.005),
+        // (3.8, 0.004),
+        // (4.0, 0.003),
+    ];
+    let star_color_buckets = [
+        ("#d6f0ff", 1),
+        ("#e0e8ff", 2),
+        ("#f8f7ff", 4),
+        ("#fff4ea", 2),
+        ("#ffe9d2", 1),
+    ];
+    let star_mag_dist = WeightedIndex::new(star_mag_buckets.iter().map(|star| star.1)).unwrap();
+    let star_color_dist = WeightedIndex::new(star_color_buckets.iter().map(|star| star.1)).unwrap();
+    let star_mags: Vec<f64> = star_mag_dist.sample_iter(&mut rng).take(n).map(|i| star_mag_buckets[i].0).collect();
+    let star_colors: Vec<&str> = star_color_dist.sample_iter(&mut rng).take(n).map(|i| star_color_buckets[i].0).collect();
+    (0..n).map(|i| {
+        // Uniform over the sphere: sin(dec), not dec, is evenly spread
+        let ra: f64 = rng.gen_range(0.0..std::f64::consts::TAU);
+        let dec = rng.gen_range(-1.0f64..1.0).asin();
+        Star {
+            coords: SkyCoord::radians(ra, dec),
+            radius: star_mags[i],
+            color: star_colors[i],
+            alpha: rng.gen_range(0.3..1.0)
+        }
+    }).collect()
+}
+
+// Light pollution and moonlight drown out the fainter (smaller) stars
+fn draw_stars(draw: &Draw, game: &Game, stars: &[Star]) {
+    let stars_visible = game.state.site().map_or(1.0, |site| site.stars_visible())
+        * (1.0 - 0.6 * game.state.weather.moonlight);
+    let faintest = STAR_RADIUS_BRIGHTEST - (STAR_RADIUS_BRIGHTEST - STAR_RADIUS_FAINTEST) * stars_visible;
+    let mut params = CanvasDrawParams::new().fill(common_colors::WHITE.to_string()).do_not_stroke(false);
+    for star in stars.iter().filter(|star| star.radius >= faintest) {
+        // Draw a star!
+        if let Some(position) = game.sky_position(star.coords) {
+            let should_stroke = star.radius >= 2.0; // adding an outline looks nice only on the larger stars
+            params = params.fill(star.color.to_string()).do_not_stroke(should_stroke).global_alpha(star.alpha);
+            draw.circle(position, star.radius, &params);
+        }
+    }
+}
+
+// A puff of cloud, drifting across the canvas. The cloudier it is, the more of them show up
+struct Cloud {
+    start: Point,
+    radius: f64,
+    speed: f64 // px per tick
+}
+
+fn make_clouds(rng: &mut SmallRng, n: usize) -> Vec<Cloud> {
+    (0..n).map(|_| Cloud {
+        start: Point::xy(rng.gen_range(0.0..GAME_CANVAS_WIDTH as f64), rng.gen_range(0.0..GAME_CANVAS_HEIGHT as f64 * 0.8)),
+        radius: rng.gen_range(30.0..90.0),
+        speed: rng.gen_range(0.5..3.0)
+    }).collect()
+}
+
+fn draw_clouds(draw: &Draw, game: &Game, clouds: &[Cloud]) {
+    let shown = (game.state.weather.clouds * clouds.len() as f64).round() as usize;
+    let params = CanvasDrawParams::new()
+        .fill(COLOR_CLOUD.to_string())
+        .do_not_stroke(true)
+        .global_alpha(0.2 + 0.5 * game.state.weather.clouds);
+    let wrap = Point::xy(GAME_CANVAS_WIDTH as f64, GAME_CANVAS_HEIGHT as f64);
+    for cloud in clouds.iter().take(shown) {
+        let drift = Point::xy(cloud.speed * game.generation as f64, 0.0);
+        let center = (cloud.start + drift).modulo(wrap);
+        draw.circle(center, cloud.radius, &params);
+    }
+}
+
+// Mark everything the player could observe right now, each kind of thing in its own way
+fn draw_objects(draw: &Draw, game: &Game) {
+    for obj in game.state.observables.iter() {
+        if let Some(position) = game.position_of(obj) {
+            draw_sprite(draw, position, obj.category.behavior().sprite(), 1.0, phase_of(game, obj));
+        }
+    }
+}
+
+fn phase_of(game: &Game, obj: &game::AstroObject) -> Option<Phase> {
+    obj.motion.and_then(|motion| motion.phase_at(game.generation))
+}
+
+fn draw_sprite(draw: &Draw, position: Point, sprite: Sprite, scale: f64, phase: Option<Phase>) {
+    let size = sprite.size * scale;
+    let fill = || CanvasDrawParams::new().fill(sprite.color.to_string()).do_not_stroke(true);
+    match sprite.shape {
+        Shape::Point => draw.circle(position, 0.6 * size, &fill()),
+        Shape::Disc => {
+            draw.circle(position, size, &fill());
+            // The night side, on the left while it's waxing (from the northern hemisphere)
+            if let Some(phase) = phase {
+                let side = if phase.waxing { -1.0 } else { 1.0 };
+                let shadow = position + Point::xy(side * 2.0 * phase.illuminated * size, 0.0);
+                let dark = CanvasDrawParams::new().fill(COLOR_SKY.to_string()).do_not_stroke(true);
+                draw.clipped_to_circle(position, size, || draw.circle(shadow, size, &dark));
+            }
+        }
+        Shape::Fuzzy => {
+            draw.circle(position, 1.6 * size, &fill().global_alpha(0.25));
+            draw.circle(position, 0.5 * size, &fill());
+        }
+        Shape::Clump => {
+            for (x, y) in [(-0.6, -0.3), (0.5, -0.5), (0.1, 0.6), (-0.2, 0.1), (0.7, 0.3)].iter() {
+                draw.circle(position + Point::xy(*x, *y).scale(size), 0.3 * size, &fill());
+            }
+        }
+        Shape::Ring => {
+            let params = CanvasDrawParams::new()
+                .stroke(sprite.color.to_string())
+                .line_width(std::cmp::max(1, (0.3 * size).round() as i32))
+                .do_not_fill(true);
+            draw.circle(position, size, &params);
+        }
+        Shape::Tail => {
+            let params = CanvasDrawParams::new().line_width(std::cmp::max(1, size.round() as i32)).do_not_stroke(true);
+            let tail = position + Point::xy(4.0, -2.0).scale(size);
+            draw.line_gradient(position, tail, sprite.color.to_string(), "transparent".to_string(), &params);
+            draw.circle(position, 0.6 * size, &fill());
+        }
+        Shape::Burst => {
+            let params = CanvasDrawParams::new().line_width(1).do_not_stroke(true);
+            for i in 0..6 {
+                let angle = i as f64 * std::f64::consts::TAU / 6.0 + 0.3;
+                let from = position + Point::rth(0.3 * size, angle);
+                let to = position + Point::rth(1.5 * size, angle);
+                draw.line_gradient(to, from, sprite.color.to_string(), "transparent".to_string(), &params);
+            }
+        }
+        Shape::Saucer => {
+            let half = Point::xy(size, 0.4 * size);
+            draw.rect_rounded(position - half, position + half, 0.4 * size, &fill());
+            draw.circle(position - Point::xy(0.0, 0.4 * size), 0.4 * size, &fill());
+        }
+    }
+}
+
+// A zoomed-in view of the sky, through the telescope the player's using. It stays
+// on the same ra and dec, following the sky as it turns
+struct Eyepiece {
+    center: SkyCoord,
+    screen: Point // where it's drawn on the canvas
+}
+
+impl Eyepiece {
+    // Centered on the sky where the player clicked, but kept inside the canvas.
+    // There's nothing to zoom in on below the horizon
+    fn at(game: &Game, point: Point) -> Option<Self> {
+        let horizontal = game.view.unproject(point).filter(|coord| coord.dec > 0.0)?;
+        let margin = EYEPIECE_RADIUS + 5.0;
+        Some(Self {
+            center: game.state.observer.ra_dec(horizontal),
+            screen: Point::xy(
+                point.x.clamp(margin, GAME_CANVAS_WIDTH as f64 - margin),
+                point.y.clamp(margin, GAME_CANVAS_HEIGHT as f64 - margin)
+            )
+        })
+    }
+
+    // Magnified according to the telescope's field of view
+    fn view(&self, game: &Game, fov: f64) -> SkyView {
+        SkyView {
+            center: game.state.observer.alt_az(self.center),
+            fov,
+            width: 2.0 * EYEPIECE_RADIUS,
+            height: 2.0 * EYEPIECE_RADIUS,
+            projection: Projection::Gnomonic
+        }
+    }
+}
+
+// The wide-field stars, the faint field stars the telescope can pick out, and
+// the objects it can see, all magnified. The field stars come from the seed, so
+// the same spot always looks the same
+fn draw_eyepiece(draw: &Draw, game: &Game, stars: &[Star], eyepiece: &Eyepiece) {
+    let fov = match game.state.field_of_view() {
+        Some(fov) => fov,
+        None => return
+    };
+    let view = eyepiece.view(game, fov);
+    let corner = eyepiece.screen - Point::xy(EYEPIECE_RADIUS, EYEPIECE_RADIUS);
+    let project = |coords: SkyCoord| view.project(game.state.observer.alt_az(coords)).map(|p| p + corner);
+    let faintest = game.state.faintest_magnitude();
+
+    draw.clipped_to_circle(eyepiece.screen, EYEPIECE_RADIUS, || {
+        draw_background(draw, game);
+
+        let mut params = CanvasDrawParams::new().do_not_stroke(true);
+        for star in stars.iter() {
+            if let Some(position) = project(star.coords) {
+                params = params.fill(star.color.to_string()).global_alpha(star.alpha);
+                draw.circle(position, star.radius + 1.0, &params);
+            }
+        }
+
+        params = params.fill(common_colors::WHITE.to_string());
+        for star in field::field_stars(game.seed, eyepiece.center, fov / 2.0, faintest) {
+            if let Some(position) = project(star.coords) {
+                // Stars the telescope can only just make out are small and dim
+                let brightness = ((faintest - star.magnitude) / 4.0).clamp(0.15, 1.0);
+                params = params.global_alpha(brightness);
+                draw.circle(position, 0.5 + 1.5 * brightness, &params);
+            }
+        }
+
+        for obj in game.state.observables.iter().chain(game.state.observed.keys()) {
+            if game.state.is_gone(obj) || game.state.best_detail(obj).is_none() {
+                continue;
+            }
+            if let Some(position) = project(obj.coords) {
+                draw_sprite(draw, position, obj.category.behavior().sprite(), EYEPIECE_SPRITE_SCALE, phase_of(game, obj));
+            }
+        }
+    });
+
+    let rim = CanvasDrawParams::new()
+        .stroke(COLOR_EYEPIECE_RIM.to_string())
+        .line_width(6)
+        .do_not_fill(true);
+    draw.circle(eyepiece.screen, EYEPIECE_RADIUS, &rim);
+}
+
+// Everything below the horizon. The view is stereographic, so the horizon's a curve
+fn draw_ground(draw: &Draw, game: &Game) {
+    let params = CanvasDrawParams::new().fill(COLOR_GROUND_AT_DUSK.to_string());
+    let mut horizon: Vec<Point> = (0..=360)
+        .filter_map(|az| game.view.project(sky::horizontal(0.0, (az as f64).to_radians())))
+        .collect();
+    horizon.sort_by(|p, q| p.x.partial_cmp(&q.x).unwrap());
+    if let (Some(first), Some(last)) = (horizon.first().copied(), horizon.last().copied()) {
+        let bottom = GAME_CANVAS_HEIGHT as f64;
+        horizon.push(Point::xy(last.x, bottom));
+        horizon.push(Point::xy(first.x, bottom));
+        draw.polygon(horizon, &params);
+    }
+}